@@ -1,23 +1,200 @@
 use log::*;
 use regex::Regex;
-use ring::digest::{Context, Digest, SHA256};
+use ring::digest::{digest, Algorithm, Context, SHA256, SHA512};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
 use std::fs;
-use std::fs::{File, DirEntry};
+use std::fs::{File, Metadata};
 use std::io::{ErrorKind, Read};
 use std::path::{Path, PathBuf};
 
 use crate::err::*;
+use crate::util::{from_hex_string, to_hex_string};
 
 
 
-#[derive(Clone)]
+/// The hash algorithms artifact ids can be based on. Claims are about an artifact hashed with
+///  one of them, and an artifact has claims under each of its hashes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// the default, and the algorithm of all artifact ids before ids were tagged with their algorithm
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 3] = [HashAlgorithm::Sha256, HashAlgorithm::Sha512, HashAlgorithm::Blake3];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 32,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    fn context(&self) -> HashContext {
+        match self {
+            HashAlgorithm::Sha256 => HashContext::Ring(Box::new(Context::new(&SHA256))),
+            HashAlgorithm::Sha512 => HashContext::Ring(Box::new(Context::new(&SHA512))),
+            HashAlgorithm::Blake3 => HashContext::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for HashAlgorithm {
+    type Err = TrustChainError;
+
+    fn from_str(s: &str) -> Result<HashAlgorithm, TrustChainError> {
+        match HashAlgorithm::ALL.iter().find(|a| a.name() == s) {
+            Some(algorithm) => Ok(*algorithm),
+            None => err!(Config, "invalid hash algorithm {:?} - it must be 'sha256', 'sha512' or 'blake3'", s),
+        }
+    }
+}
+
+/// An artifact's hash together with the algorithm it was calculated with, written as e.g.
+///  'sha512:<hex digest>', or as 'tree-sha512:<hex digest>' for folders
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ArtifactId {
-    pub hash: Digest,
+    pub algorithm: HashAlgorithm,
+    pub hash: Vec<u8>,
+    /// whether the artifact is a folder hashed in the tree format (see `hash_folder`) rather than
+    ///  a file
+    pub tree: bool,
+}
+
+impl ArtifactId {
+    pub fn new(algorithm: HashAlgorithm, hash: Vec<u8>) -> Result<ArtifactId, TrustChainError> {
+        if hash.len() != algorithm.digest_len() {
+            return err!(InvalidArtifactId, "a {} hash has {} bytes, not {}", algorithm, algorithm.digest_len(), hash.len());
+        }
+        Ok(ArtifactId { algorithm, hash, tree: false })
+    }
+
+    /// The hex digest is parsed separately from the algorithm, for formats that store them
+    ///  separately
+    pub fn from_hex(algorithm: HashAlgorithm, hex: &str) -> Result<ArtifactId, TrustChainError> {
+        match from_hex_string(hex) {
+            Some(hash) => ArtifactId::new(algorithm, hash),
+            None => err!(InvalidArtifactId, "'{}' is not a valid {} hash", hex, algorithm),
+        }
+    }
+}
+
+impl std::fmt::Display for ArtifactId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tree {
+            f.write_str("tree-")?;
+        }
+        write!(f, "{}:{}", self.algorithm, to_hex_string(&self.hash))
+    }
+}
+
+/// Parses '[tree-]algorithm:hex digest'. A plain hex digest is a SHA-256 hash of a file, as
+///  artifact ids were written before they were tagged with their algorithm.
+impl std::str::FromStr for ArtifactId {
+    type Err = TrustChainError;
+
+    fn from_str(s: &str) -> Result<ArtifactId, TrustChainError> {
+        let (tree, tagged) = match s.strip_prefix("tree-") {
+            Some(tagged) => (true, tagged),
+            None => (false, s),
+        };
+        let id = match tagged.split_once(':') {
+            Some((algorithm, hex)) => match algorithm.parse() {
+                Ok(algorithm) => ArtifactId::from_hex(algorithm, hex)?,
+                Err(_) => return err!(InvalidArtifactId, "'{}' is not a valid artifact hash - unsupported hash algorithm {:?}", s, algorithm),
+            },
+            None if !tree => ArtifactId::from_hex(HashAlgorithm::Sha256, s)?,
+            None => return err!(InvalidArtifactId, "'{}' is not a valid artifact hash - the hash algorithm is missing", s),
+        };
+        Ok(ArtifactId { tree, ..id })
+    }
+}
+
+enum HashContext {
+    Ring(Box<Context>),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashContext {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            HashContext::Ring(context) => context.update(data),
+            HashContext::Blake3(hasher) => { hasher.update(data); },
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        match self {
+            HashContext::Ring(context) => context.finish().as_ref().to_vec(),
+            HashContext::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Feeds an artifact's content to several hash functions, so that it needs to be read only once
+struct Hasher {
+    contexts: Vec<HashContext>,
+}
+
+impl Hasher {
+    fn new(contexts: Vec<HashContext>) -> Hasher {
+        Hasher { contexts }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for context in &mut self.contexts {
+            context.update(data);
+        }
+    }
+
+    fn finish(self) -> Vec<Vec<u8>> {
+        self.contexts.into_iter().map(HashContext::finish).collect()
+    }
+}
+
+/// Hashes with several algorithms in a single pass, returning the ids in the algorithms' order.
+///  `input` returns whether it hashed a folder in the tree format.
+fn hash_with<F>(algorithms: &[HashAlgorithm], input: F) -> Result<Vec<ArtifactId>, TrustChainError> where F: FnOnce(&mut Hasher) -> Result<bool, TrustChainError> {
+    let mut hasher = Hasher::new(algorithms.iter().map(HashAlgorithm::context).collect());
+    let tree = input(&mut hasher)?;
+    algorithms.iter().zip(hasher.finish())
+        .map(|(algorithm, hash)| Ok(ArtifactId { tree, ..ArtifactId::new(*algorithm, hash)? }))
+        .collect()
+}
+
+/// Hashes a file, or a folder in the canonical tree format (see `hash_folder`). A symbolic link
+///  given as the path is followed.
+pub fn hash_path(path: &Path, algorithms: &[HashAlgorithm]) -> Result<Vec<ArtifactId>, TrustChainError> {
+    hash_with(algorithms, |hasher| hash_file_or_folder(hasher, path))
 }
-impl Copy for ArtifactId {}
 
 pub enum ArtifactRepository {
     Maven(MavenRepository),
+    Cargo(CargoRepository),
+    Npm(NpmRepository),
+    Local(LocalRepository),
+    Oci(OciRepository),
+    Python(PythonRepository),
 }
 
 impl ArtifactRepository {
@@ -25,73 +202,601 @@ impl ArtifactRepository {
         ArtifactRepository::Maven (MavenRepository::new(root))
     }
 
-    pub fn do_hash(&self, artifact_id: &str) -> Result<Digest, TrustChainError> {
-        use ArtifactRepository::*;
+    /// `root` is the Cargo home folder, i.e. usually '~/.cargo'. If `unpacked` is true, artifacts
+    ///  are the unpacked source folders rather than the '.crate' files.
+    pub fn new_cargo(root: PathBuf, unpacked: bool) -> ArtifactRepository {
+        ArtifactRepository::Cargo (CargoRepository::new(root, unpacked))
+    }
 
-        let mut context = Context::new(&SHA256);
+    /// `root` is either npm's cache folder (usually '~/.npm') or, if `unpacked` is true, a
+    ///  'node_modules' folder with unpacked packages
+    pub fn new_npm(root: PathBuf, unpacked: bool) -> ArtifactRepository {
+        ArtifactRepository::Npm (NpmRepository::new(root, unpacked))
+    }
+
+    /// Files and folders in the local file system, identified by their paths relative to `root`
+    pub fn new_local(root: PathBuf) -> ArtifactRepository {
+        ArtifactRepository::Local (LocalRepository { root })
+    }
+
+    /// `root` is an OCI image layout, either a folder or a tarball
+    pub fn new_oci(root: PathBuf) -> ArtifactRepository {
+        ArtifactRepository::Oci (OciRepository { root })
+    }
+
+    /// `root` is a folder with Python wheels and sdists, which are searched in its subfolders as well
+    pub fn new_python(root: PathBuf) -> ArtifactRepository {
+        ArtifactRepository::Python (PythonRepository { root })
+    }
+
+    pub fn do_hash(&self, artifact_id: &str, algorithm: HashAlgorithm) -> Result<ArtifactId, TrustChainError> {
+        let mut result = hash_with(&[algorithm], |hasher| self.hash_into(artifact_id, hasher))?;
+        Ok(result.remove(0))
+    }
+
+    /// The artifact's ids for all supported hash algorithms, in the order of `HashAlgorithm::ALL`,
+    ///  for looking up claims regardless of the algorithm their signers chose
+    pub fn do_hash_all(&self, artifact_id: &str) -> Result<Vec<ArtifactId>, TrustChainError> {
+        hash_with(&HashAlgorithm::ALL, |hasher| self.hash_into(artifact_id, hasher))
+    }
+
+    /// Hashes an artifact with an algorithm that artifact ids are not based on, e.g. to compare
+    ///  it to a checksum recorded by a build tool
+    pub fn do_hash_with(&self, artifact_id: &str, algorithm: &'static Algorithm) -> Result<Vec<u8>, TrustChainError> {
+        let mut hasher = Hasher::new(vec!(HashContext::Ring(Box::new(Context::new(algorithm)))));
+        self.hash_into(artifact_id, &mut hasher)?;
+        Ok(hasher.finish().remove(0))
+    }
+
+    /// Returns whether the artifact is a folder, which is hashed in the tree format
+    fn hash_into(&self, artifact_id: &str, hasher: &mut Hasher) -> Result<bool, TrustChainError> {
+        use ArtifactRepository::*;
 
         match self {
             Maven(repo) => {
                 let path = repo.id_to_path(artifact_id)?;
-                hash_file(&mut context, &path)?;
-                Ok(context.finish())
+                hash_file(hasher, &path).map(|_| false)
+            }
+            Cargo(repo) => {
+                let path = repo.id_to_path(artifact_id)?;
+                if repo.unpacked {
+                    hash_folder(hasher, &path).map(|_| true)
+                }
+                else {
+                    hash_file(hasher, &path).map(|_| false)
+                }
+            }
+            Npm(repo) => {
+                let path = repo.id_to_path(artifact_id)?;
+                if repo.unpacked {
+                    hash_folder(hasher, &path).map(|_| true)
+                }
+                else {
+                    hash_file(hasher, &path).map(|_| false)
+                }
+            }
+            Local(repo) => hash_file_or_folder(hasher, &repo.root.join(artifact_id)),
+            Oci(repo) => repo.hash_artifact(artifact_id, hasher).map(|_| false),
+            Python(repo) => {
+                let path = repo.id_to_path(artifact_id)?;
+                hash_file(hasher, &path).map(|_| false)
             }
         }
     }
 }
 
-fn hash_file(context: &mut Context, path: &Path) -> Result<(),TrustChainError> {
+fn hash_file(hasher: &mut Hasher, path: &Path) -> Result<(),TrustChainError> {
+    hash_file_content(hasher, path).map(|_| ())
+}
+
+/// Returns the number of bytes hashed
+fn hash_file_content(hasher: &mut Hasher, path: &Path) -> Result<u64,TrustChainError> {
     debug!("hashing file {:?}", path);
     let mut f = io_guarded!(File::open(path), ArtifactNotFound, "artifact not found at '{:?}'", path);
+    hash_reader(hasher, &mut f, &path)
+}
+
+/// Returns the number of bytes hashed. `name` identifies the content in error messages.
+fn hash_reader(hasher: &mut Hasher, f: &mut dyn Read, name: &dyn std::fmt::Debug) -> Result<u64,TrustChainError> {
     let mut buf = [0u8;65536];
+    let mut len = 0u64;
 
     loop {
         match f.read(&mut buf) {
-            Ok(0) => return Ok(()),
-            Ok(n) => context.update(&buf[..n]),
+            Ok(0) => return Ok(len),
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                len += n as u64;
+            },
             Err(ref e) if e.kind() == ErrorKind::Interrupted => {},
             Err(e) => {
-                return err!(ArtifactReadError, "error reading artifact '{:?}' @ {:?}", path, e);
+                return err!(ArtifactReadError, "error reading artifact '{:?}' @ {:?}", name, e);
             }
         }
     }
 }
 
-fn hash_folder(context: &mut Context, path: &Path) -> Result<(), TrustChainError> {
+/// Returns whether the path is a folder, which is hashed in the tree format
+fn hash_file_or_folder(hasher: &mut Hasher, path: &Path) -> Result<bool, TrustChainError> {
+    let metadata = io_guarded!(fs::metadata(path), ArtifactNotFound, "artifact not found at '{:?}'", path);
+    if metadata.is_dir() {
+        hash_folder(hasher, path).map(|_| true)
+    }
+    else {
+        hash_file(hasher, path).map(|_| false)
+    }
+}
+
+/// prepended to a folder's serialization to separate it from other uses of the hash. It does not
+///  keep a folder's hash from being equal to a file's: a file that starts with this header,
+///  followed by a folder's serialization, has the same hash as that folder. Tree hashes are told
+///  apart from file hashes by the 'tree-' tag of their artifact ids instead.
+const TREE_HASH_HEADER: &[u8] = b"trust-chain-checker tree v1\n";
+
+/// Hashes a folder in a canonical format that other tools can reproduce. The hash is calculated
+///  over the following serialization:
+///
+/// ```text
+/// tree   = "trust-chain-checker tree v1\n" folder
+/// folder = u32(number of entries) entry*
+/// entry  = "f" name u64(length) content     a regular file
+///        | "x" name u64(length) content     a regular file with any execute permission bit set
+///        | "l" name bytes                   a symbolic link with its target, which is not followed
+///        | "d" name folder                  a folder - empty folders are part of the hash
+/// name   = bytes
+/// bytes  = u32(length) byte*
+/// ```
+///
+/// Integers are big-endian. A folder's entries are sorted by their names' bytes. On Unix, names
+///  and link targets are taken as the raw bytes, so they need not be UTF-8; elsewhere they must be
+///  valid Unicode and are encoded as UTF-8. Permission bits other than the execute bits, owners
+///  and timestamps are not part of the hash. Hard links are hashed like separate files, i.e. each
+///  with its content. Other kinds of files like sockets or devices cannot be hashed.
+fn hash_folder(hasher: &mut Hasher, path: &Path) -> Result<(), TrustChainError> {
+    hasher.update(TREE_HASH_HEADER);
+    hash_folder_entries(hasher, path)
+}
+
+fn hash_folder_entries(hasher: &mut Hasher, path: &Path) -> Result<(), TrustChainError> {
     debug!("hashing folder {:?}", path);
 
     let mut entries = Vec::new();
     for entry in io_guarded!(fs::read_dir(path), ArtifactFolderReadError, "Cannot read artifact folder {:?}", path) {
-        let entry: &DirEntry = io_guarded!(&entry, ArtifactFolderReadError, "Cannot read artifact folder entry {:?}", &entry);
-        let path = entry.path();
+        let entry = io_guarded!(entry, ArtifactFolderReadError, "Cannot read an entry of artifact folder {:?}", path);
+        let entry_path = entry.path();
+        entries.push((os_str_bytes(&entry.file_name(), &entry_path)?, entry_path));
+    }
+    entries.sort();
+
+    hasher.update(&(entries.len() as u32).to_be_bytes());
+    for (name, path) in entries {
+        let metadata = io_guarded!(fs::symlink_metadata(&path), ArtifactReadError, "Cannot read metadata of {:?}", &path);
+        let file_type = metadata.file_type();
 
-        match path.file_name() {
-            None => continue,
-            Some(s) if s == "." => continue,
-            Some(_) => entries.push(path)
+        if file_type.is_symlink() {
+            let target = io_guarded!(fs::read_link(&path), ArtifactReadError, "Cannot read link {:?}", &path);
+            hasher.update(b"l");
+            hash_bytes(hasher, &name);
+            hash_bytes(hasher, &os_str_bytes(target.as_os_str(), &path)?);
+        }
+        else if file_type.is_dir() {
+            hasher.update(b"d");
+            hash_bytes(hasher, &name);
+            hash_folder_entries(hasher, &path)?;
+        }
+        else if file_type.is_file() {
+            hasher.update(if is_executable(&metadata) { b"x" } else { b"f" });
+            hash_bytes(hasher, &name);
+            hasher.update(&metadata.len().to_be_bytes());
+            let len = hash_file_content(hasher, &path)?;
+            if len != metadata.len() {
+                return err!(ArtifactReadError, "{:?} was modified while it was hashed", &path);
+            }
+        }
+        else {
+            return err!(ArtifactReadError, "{:?} is neither a file, a folder nor a symbolic link and cannot be hashed", &path);
         }
     }
 
-    entries.sort_by_cached_key(|p| p.file_name().unwrap().to_str().unwrap().to_string()); //TODO error handling - UTF-8 conversions, non-UTF-8 characters?!
+    Ok(())
+}
+
+fn hash_bytes(hasher: &mut Hasher, bytes: &[u8]) {
+    hasher.update(&(bytes.len() as u32).to_be_bytes());
+    hasher.update(bytes);
+}
+
+#[cfg(unix)]
+fn os_str_bytes(s: &OsStr, _path: &Path) -> Result<Vec<u8>, TrustChainError> {
+    use std::os::unix::ffi::OsStrExt;
+    Ok(s.as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn os_str_bytes(s: &OsStr, path: &Path) -> Result<Vec<u8>, TrustChainError> {
+    match s.to_str() {
+        Some(s) => Ok(s.as_bytes().to_vec()),
+        None => err!(ArtifactReadError, "{:?} contains a name that is not valid Unicode", path),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
 
-    for path in entries {
-        if path.read_link().is_ok() {
-            //TODO hard links?
-            debug!("skipping link {:?}", path);
-            continue;
+#[cfg(not(unix))]
+fn is_executable(_metadata: &Metadata) -> bool {
+    false
+}
+
+/// Files and folders in the local file system, e.g. vendored source trees or release tarballs
+pub struct LocalRepository {
+    root: PathBuf,
+}
+
+/// Container images in an OCI image layout (cf. https://github.com/opencontainers/image-spec/blob/main/image-layout.md),
+///  either a folder or an uncompressed tarball like the ones written by 'docker save' since Docker 25.
+///
+/// Images are identified as 'name:tag', 'name@<digest>' or just by their digest, e.g. 'sha256:<hex>'.
+///  An image's artifact is its manifest (or, for multi-platform images, its image index), so its
+///  SHA-256 hash is the image's digest. A single layer is identified by appending '#<index>',
+///  counting from 0, or '#<layer digest>' to the image; its artifact is the layer blob as stored,
+///  i.e. usually compressed, so that its hash is the layer's digest.
+pub struct OciRepository {
+    root: PathBuf,
+}
+
+/// the annotations in 'index.json' that name an image - the former is set by 'docker save',
+///  the latter by most other tools, often with the tag only
+const OCI_IMAGE_NAME_ANNOTATIONS: [&str; 2] = ["io.containerd.image.name", "org.opencontainers.image.ref.name"];
+
+#[derive(Deserialize)]
+struct OciDescriptor {
+    digest: String,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+}
+
+impl OciDescriptor {
+    /// `reference` matches an image name as given, or as Docker completes it with the default
+    ///  registry, namespace and tag
+    fn names_image(&self, reference: &str) -> bool {
+        let completed = complete_docker_reference(reference);
+        OCI_IMAGE_NAME_ANNOTATIONS.iter()
+            .filter_map(|a| self.annotations.get(*a))
+            .any(|name| name == reference || name == &completed)
+    }
+}
+
+/// 'alpine' is short for 'docker.io/library/alpine:latest', 'foo/bar:1' for 'docker.io/foo/bar:1'
+fn complete_docker_reference(reference: &str) -> String {
+    let mut result = match reference.split_once('/') {
+        Some((registry, _)) if registry.contains('.') || registry.contains(':') || registry == "localhost" => reference.to_string(),
+        Some(_) => format!("docker.io/{}", reference),
+        None => format!("docker.io/library/{}", reference),
+    };
+    if !result.rsplit('/').next().unwrap_or_default().contains(':') {
+        result.push_str(":latest");
+    }
+    result
+}
+
+/// An image manifest or an image index - only the descriptors are needed, for both
+#[derive(Deserialize)]
+struct OciManifest {
+    #[serde(default)]
+    manifests: Vec<OciDescriptor>,
+    config: Option<OciDescriptor>,
+    #[serde(default)]
+    layers: Vec<OciDescriptor>,
+}
+
+impl OciManifest {
+    fn is_index(&self) -> bool {
+        self.config.is_none()
+    }
+}
+
+impl OciRepository {
+    fn hash_artifact(&self, artifact_id: &str, hasher: &mut Hasher) -> Result<(), TrustChainError> {
+        let (image, layer) = match artifact_id.split_once('#') {
+            Some((image, layer)) => (image, Some(layer)),
+            None => (artifact_id, None),
+        };
+
+        let digest = self.resolve(image)?;
+        let manifest = self.read_manifest(&digest)?;
+
+        match layer {
+            None => {
+                // the manifest vouches for the image's content only if the content matches it
+                self.verify_content(&manifest)?;
+                self.hash_blob(hasher, &digest)
+            },
+            Some(_) if manifest.is_index() => err!(InvalidArtifactId, "{} is an image index for several platforms - please identify the platform's image by its digest", image),
+            Some(layer) => {
+                let descriptor = match layer.parse::<usize>() {
+                    Ok(idx) => manifest.layers.get(idx),
+                    Err(_) => manifest.layers.iter().find(|d| d.digest == layer),
+                };
+                let layer_digest = match descriptor {
+                    Some(descriptor) => parse_oci_digest(&descriptor.digest)?,
+                    None => return err!(ArtifactNotFound, "image {} has no layer {}", image, layer),
+                };
+                self.verify_blob(&layer_digest)?;
+                self.hash_blob(hasher, &layer_digest)
+            },
+        }
+    }
+
+    /// The digest of the manifest or image index a reference points to
+    fn resolve(&self, reference: &str) -> Result<ArtifactId, TrustChainError> {
+        if let Some((_, digest)) = reference.rsplit_once('@') {
+            return parse_oci_digest(digest);
+        }
+        if let Ok(digest) = parse_oci_digest(reference) {
+            return Ok(digest);
         }
 
-        context.update(path.file_name().unwrap().to_str().unwrap().as_bytes());
+        let index: OciManifest = self.read_json("index.json")?;
+        let mut candidates = index.manifests.iter().filter(|d| d.names_image(reference));
+        match (candidates.next(), candidates.next()) {
+            (Some(descriptor), None) => parse_oci_digest(&descriptor.digest),
+            (Some(_), Some(_)) => err!(InvalidArtifactId, "image {} is ambiguous in {:?} - please identify it by its digest", reference, &self.root),
+            (None, _) => err!(ArtifactNotFound, "image {} not found in {:?}", reference, &self.root),
+        }
+    }
 
-        if path.is_dir() {
-            hash_folder(context, &path)?;
+    /// Checks that the blobs an image manifest refers to match their digests. Of an image index,
+    ///  only the images that are present are checked since layouts often contain only some
+    ///  platforms' images.
+    fn verify_content(&self, manifest: &OciManifest) -> Result<(), TrustChainError> {
+        if manifest.is_index() {
+            for descriptor in &manifest.manifests {
+                let digest = parse_oci_digest(&descriptor.digest)?;
+                match self.read_manifest(&digest) {
+                    Ok(child) => self.verify_content(&child)?,
+                    Err(TrustChainError { kind: TrustChainErrorKind::ArtifactNotFound, .. }) => debug!("image {} is not in {:?}", digest, &self.root),
+                    Err(e) => return Err(e),
+                }
+            }
+            return Ok(());
         }
-        if path.is_file() {
-            hash_file(context, &path)?;
+
+        for descriptor in manifest.config.iter().chain(&manifest.layers) {
+            self.verify_blob(&parse_oci_digest(&descriptor.digest)?)?;
         }
+        Ok(())
     }
 
-    Ok(())
+    fn verify_blob(&self, digest: &ArtifactId) -> Result<(), TrustChainError> {
+        debug!("verifying blob {}", digest);
+        let actual = hash_with(&[digest.algorithm], |hasher| self.hash_blob(hasher, digest).map(|_| false))?;
+        if actual[0] != *digest {
+            return err!(ArtifactReadError, "blob {} in {:?} does not match its digest - it may have been tampered with", digest, &self.root);
+        }
+        Ok(())
+    }
+
+    fn hash_blob(&self, hasher: &mut Hasher, digest: &ArtifactId) -> Result<(), TrustChainError> {
+        let name = oci_blob_name(digest);
+        self.read_file(&name, |f| hash_reader(hasher, f, &name).map(|_| ()))
+    }
+
+    fn read_manifest(&self, digest: &ArtifactId) -> Result<OciManifest, TrustChainError> {
+        let name = oci_blob_name(digest);
+        let data = self.read_file(&name, |f| {
+            let mut data = Vec::new();
+            io_guarded!(f.read_to_end(&mut data), ArtifactReadError, "error reading {} in {:?}", &name, &self.root);
+            Ok(data)
+        })?;
+
+        let actual = hash_with(&[digest.algorithm], |hasher| { hasher.update(&data); Ok(false) })?;
+        if actual[0] != *digest {
+            return err!(ArtifactReadError, "manifest {} in {:?} does not match its digest - it may have been tampered with", digest, &self.root);
+        }
+        Ok(io_guarded!(serde_json::from_slice(&data), ArtifactReadError, "error parsing manifest {} in {:?}", digest, &self.root))
+    }
+
+    fn read_json<T: DeserializeOwned>(&self, name: &str) -> Result<T, TrustChainError> {
+        self.read_file(name, |f| Ok(io_guarded!(serde_json::from_reader(f), ArtifactReadError, "error parsing {} in {:?}", name, &self.root)))
+    }
+
+    /// Passes a file of the layout to `f`. In a tarball, the file is looked up by scanning the
+    ///  tarball's entries.
+    fn read_file<T, F>(&self, name: &str, f: F) -> Result<T, TrustChainError> where F: FnOnce(&mut dyn Read) -> Result<T, TrustChainError> {
+        if self.root.is_dir() {
+            let path = self.root.join(name);
+            let mut file = io_guarded!(File::open(&path), ArtifactNotFound, "{} not found in {:?}", name, &self.root);
+            return f(&mut file);
+        }
+
+        let file = io_guarded!(File::open(&self.root), ArtifactNotFound, "OCI image layout {:?} not found", &self.root);
+        let mut archive = tar::Archive::new(file);
+        for entry in io_guarded!(archive.entries_with_seek(), ArtifactReadError, "error reading tarball {:?}", &self.root) {
+            let mut entry = io_guarded!(entry, ArtifactReadError, "error reading tarball {:?}", &self.root);
+            let path = io_guarded!(entry.path(), ArtifactReadError, "invalid entry in tarball {:?}", &self.root).to_path_buf();
+            if path.strip_prefix(".").unwrap_or(&path) == Path::new(name) {
+                return f(&mut entry);
+            }
+        }
+        err!(ArtifactNotFound, "{} not found in {:?}", name, &self.root)
+    }
+}
+
+/// Digests in OCI descriptors always state their algorithm, e.g. 'sha256:<hex>'
+fn parse_oci_digest(digest: &str) -> Result<ArtifactId, TrustChainError> {
+    if !digest.contains(':') {
+        return err!(InvalidArtifactId, "'{}' is not a valid digest", digest);
+    }
+    digest.parse()
+}
+
+fn oci_blob_name(digest: &ArtifactId) -> String {
+    format!("blobs/{}/{}", digest.algorithm, to_hex_string(&digest.hash))
+}
+
+/// Python distributions, i.e. wheels and sdists, in a folder and its subfolders. This can be a
+///  wheelhouse, e.g. written by 'pip download -d', or pip's cache of the wheels it built. pip's
+///  HTTP cache stores downloaded files under hashes of their URLs, so they cannot be found there.
+///
+/// A distribution is identified as 'name==version' if it is the only one of that version, and
+///  otherwise by its file name, e.g. 'requests-2.31.0-py3-none-any.whl'.
+pub struct PythonRepository {
+    root: PathBuf,
+}
+
+impl PythonRepository {
+    fn id_to_path(&self, artifact_id: &str) -> Result<PathBuf, TrustChainError> {
+        if let Some((name, version)) = artifact_id.split_once("==") {
+            let mut distributions = python_distributions(&self.root, name, version)?;
+            return match distributions.len() {
+                0 => err!(ArtifactNotFound, "no distribution of {} found in {:?}", artifact_id, &self.root),
+                1 => Ok(distributions.remove(0)),
+                _ => {
+                    let names: Vec<String> = distributions.iter()
+                        .filter_map(|d| d.file_name())
+                        .map(|n| n.to_string_lossy().to_string())
+                        .collect();
+                    err!(InvalidArtifactId, "there are several distributions of {} - please choose one by its file name: {}", artifact_id, names.join(", "))
+                }
+            };
+        }
+
+        if python_distribution_name(artifact_id).is_none() {
+            return err!(InvalidArtifactId, "'{}' is neither 'name==version' nor the file name of a wheel or sdist", artifact_id);
+        }
+        match python_files(&self.root)?.into_iter().find(|f| f.file_name() == Some(OsStr::new(artifact_id))) {
+            Some(path) => Ok(path),
+            None => err!(ArtifactNotFound, "{} not found in {:?}", artifact_id, &self.root),
+        }
+    }
+}
+
+/// All wheels and sdists of a version of a Python package in a folder and its subfolders, sorted
+///  by their file names. Package names are compared in their normalized form (cf. PEP 503).
+pub fn python_distributions(root: &Path, name: &str, version: &str) -> Result<Vec<PathBuf>, TrustChainError> {
+    let name = normalize_python_name(name);
+
+    let mut result: Vec<PathBuf> = python_files(root)?.into_iter()
+        .filter(|f| {
+            let file_name = f.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            match python_distribution_name(&file_name) {
+                Some((n, v)) => normalize_python_name(n) == name && v == version,
+                None => false,
+            }
+        })
+        .collect();
+    result.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    Ok(result)
+}
+
+/// Symbolic links to files are followed, symbolic links to folders are not, as they may form cycles
+fn python_files(folder: &Path) -> Result<Vec<PathBuf>, TrustChainError> {
+    let mut result = Vec::new();
+    for entry in io_guarded!(fs::read_dir(folder), ArtifactFolderReadError, "Cannot read Python repository folder {:?}", folder) {
+        let entry = io_guarded!(entry, ArtifactFolderReadError, "Cannot read an entry of folder {:?}", folder);
+        let file_type = io_guarded!(entry.file_type(), ArtifactFolderReadError, "Cannot read the type of {:?}", entry.path());
+        let path = entry.path();
+        if file_type.is_dir() {
+            result.extend(python_files(&path)?);
+        }
+        else if file_type.is_symlink() && path.is_dir() {
+            debug!("skipping symbolic link to a folder {:?}", path);
+        }
+        else if path.is_file() {
+            result.push(path);
+        }
+    }
+    Ok(result)
+}
+
+/// The package name and version from a wheel's file name
+///  ('{name}-{version}(-{build})?-{python}-{abi}-{platform}.whl') or an sdist's ('{name}-{version}.tar.gz'
+///  or '.zip'). Older sdists have dashes in their names, so their version follows the last dash.
+fn python_distribution_name(file_name: &str) -> Option<(&str, &str)> {
+    if let Some(stem) = file_name.strip_suffix(".whl") {
+        let parts: Vec<&str> = stem.split('-').collect();
+        return match parts.len() {
+            5 | 6 => Some((parts[0], parts[1])),
+            _ => None,
+        };
+    }
+    let stem = file_name.strip_suffix(".tar.gz")
+        .or_else(|| file_name.strip_suffix(".zip"))?;
+    stem.rsplit_once('-')
+        .filter(|(name, version)| !name.is_empty() && !version.is_empty())
+}
+
+/// Lower case, with runs of '-', '_' and '.' replaced by a single '-' (cf. PEP 503)
+pub fn normalize_python_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c == '-' || c == '_' || c == '.' {
+            if !result.ends_with('-') {
+                result.push('-');
+            }
+        }
+        else {
+            result.extend(c.to_lowercase());
+        }
+    }
+    result
+}
+
+/// The parts of a Maven artifact identifier 'group:artifact[:packaging[:classifier]]:version'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MavenCoordinates {
+    pub group_id: String,
+    pub artifact_id: String,
+    pub packaging: String,
+    pub classifier: Option<String>,
+    pub version: String,
+}
+
+impl MavenCoordinates {
+    /// The file extension for a packaging type, cf. Maven's artifact handlers
+    fn extension(&self) -> &str {
+        match self.packaging.as_str() {
+            "bundle" | "ejb" | "ejb-client" | "java-source" | "javadoc" | "maven-plugin" | "test-jar" => "jar",
+            other => other,
+        }
+    }
+
+    fn effective_classifier(&self) -> Option<&str> {
+        match (&self.classifier, self.packaging.as_str()) {
+            (Some(c), _) => Some(c),
+            (None, "test-jar") => Some("tests"),
+            (None, "java-source") => Some("sources"),
+            (None, "javadoc") => Some("javadoc"),
+            (None, "ejb-client") => Some("client"),
+            (None, _) => None,
+        }
+    }
+
+    fn is_snapshot(&self) -> bool {
+        self.version.ends_with("-SNAPSHOT")
+    }
+
+    /// The name of the artifact's file for a given (possibly timestamped) version
+    fn file_name(&self, version: &str) -> String {
+        match self.effective_classifier() {
+            Some(classifier) => format!("{}-{}-{}.{}", self.artifact_id, version, classifier, self.extension()),
+            None => format!("{}-{}.{}", self.artifact_id, version, self.extension()),
+        }
+    }
+}
+
+impl std::fmt::Display for MavenCoordinates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.group_id, self.artifact_id, self.packaging)?;
+        if let Some(classifier) = &self.classifier {
+            write!(f, ":{}", classifier)?;
+        }
+        write!(f, ":{}", self.version)
+    }
 }
 
 pub struct MavenRepository {
@@ -104,40 +809,774 @@ impl MavenRepository {
     fn new(root: PathBuf) -> MavenRepository {
         MavenRepository {
             root,
-            regex_id: Regex::new(r"^([^:]+):([^:]+):([^:]+)$").unwrap(),
+            regex_id: Regex::new(r"^([^:]+):([^:]+)(?::([^:]+)(?::([^:]+))?)?:([^:]+)$").unwrap(),
             regex_group: Regex::new(r"([^.]+)").unwrap(),
         }
     }
 
-    fn id_to_path(&self, artifact_id: &str) -> Result<PathBuf, TrustChainError> {
+    pub fn parse_id(&self, artifact_id: &str) -> Result<MavenCoordinates, TrustChainError> {
         let captures = match self.regex_id.captures(artifact_id) {
             Some(c) => c,
             None => return err!(InvalidArtifactId, "'{}' is not a valid Maven artifact identifier", artifact_id),
         };
 
-        let group_id = &captures[1];
-        let art_id = &captures[2];
-        let version = &captures[3];
+        Ok(MavenCoordinates {
+            group_id: captures[1].to_string(),
+            artifact_id: captures[2].to_string(),
+            packaging: captures.get(3).map_or("jar", |m| m.as_str()).to_string(),
+            classifier: captures.get(4).map(|m| m.as_str().to_string()),
+            version: captures[5].to_string(),
+        })
+    }
 
-        let mut jar_file = art_id.to_string();
-        jar_file.push('-');
-        jar_file.push_str(version);
-        jar_file.push_str(".jar");
+    fn id_to_path(&self, artifact_id: &str) -> Result<PathBuf, TrustChainError> {
+        let coordinates = self.parse_id(artifact_id)?;
 
         let mut result = self.root.clone();
 
-        for group_seg in self.regex_group.captures_iter(group_id) {
+        for group_seg in self.regex_group.captures_iter(&coordinates.group_id) {
             result.push(&group_seg[0]);
         }
 
-        result.push(art_id);
-        result.push(version);
-        result.push(jar_file);
+        result.push(&coordinates.artifact_id);
+        result.push(&coordinates.version);
+
+        let file_name = if coordinates.is_snapshot() {
+            match self.snapshot_version(&result, &coordinates)? {
+                Some(timestamped_version) => coordinates.file_name(&timestamped_version),
+                None => coordinates.file_name(&coordinates.version),
+            }
+        }
+        else {
+            coordinates.file_name(&coordinates.version)
+        };
+        result.push(file_name);
 
         Ok(result)
     }
+
+    /// Resolves a SNAPSHOT version to the timestamped version of the actual file, based on the
+    ///  snapshot metadata in the version folder. 'maven-metadata-local.xml' is written for
+    ///  locally installed snapshots, 'maven-metadata-<repository id>.xml' for downloaded ones.
+    fn snapshot_version(&self, version_folder: &Path, coordinates: &MavenCoordinates) -> Result<Option<String>, TrustChainError> {
+        let mut metadata_files = vec!(version_folder.join("maven-metadata-local.xml"));
+        if let Ok(entries) = fs::read_dir(version_folder) {
+            let mut remote_files: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    let name = p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    name.starts_with("maven-metadata-") && name.ends_with(".xml") && name != "maven-metadata-local.xml"
+                })
+                .collect();
+            remote_files.sort();
+            metadata_files.extend(remote_files);
+        }
+
+        for metadata_file in metadata_files.iter().filter(|f| f.is_file()) {
+            if let Some(version) = snapshot_version_from_metadata(metadata_file, coordinates)? {
+                debug!("{} resolved to {} by {:?}", coordinates, version, metadata_file);
+                return Ok(Some(version));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// <metadata>
+///   <versioning>
+///     <snapshotVersions>
+///       <snapshotVersion>
+///         <classifier>sources</classifier>
+///         <extension>jar</extension>
+///         <value>1.0-20200101.123456-1</value>
+///       </snapshotVersion>
+fn snapshot_version_from_metadata(metadata_file: &Path, coordinates: &MavenCoordinates) -> Result<Option<String>, TrustChainError> {
+    let xml = io_guarded!(fs::read_to_string(metadata_file), ArtifactReadError, "error reading Maven metadata {:?}", metadata_file);
+    let doc = io_guarded!(roxmltree::Document::parse(&xml), ArtifactReadError, "error parsing Maven metadata {:?}", metadata_file);
+
+    let child_text = |node: roxmltree::Node, name: &str| node.children()
+        .find(|c| c.has_tag_name(name))
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string());
+
+    for snapshot_version in doc.descendants().filter(|n| n.has_tag_name("snapshotVersion")) {
+        if child_text(snapshot_version, "extension").as_deref() == Some(coordinates.extension())
+            && child_text(snapshot_version, "classifier").as_deref() == coordinates.effective_classifier() {
+            return Ok(child_text(snapshot_version, "value"));
+        }
+    }
+    Ok(None)
 }
 
+/// Crates downloaded by Cargo, identified as 'name:version' or 'name:version:registry'. The
+///  registry is the name of the registry's index folder (e.g. 'index.crates.io') and can be
+///  omitted if the crate is present for only one registry.
 pub struct CargoRepository {
     root: PathBuf,
+    unpacked: bool,
+    regex_id: Regex,
+}
+
+impl CargoRepository {
+    fn new(root: PathBuf, unpacked: bool) -> CargoRepository {
+        CargoRepository {
+            root,
+            unpacked,
+            regex_id: Regex::new(r"^([^:]+):([^:]+)(:([^:]+))?$").unwrap(),
+        }
+    }
+
+    fn id_to_path(&self, artifact_id: &str) -> Result<PathBuf, TrustChainError> {
+        let captures = match self.regex_id.captures(artifact_id) {
+            Some(c) => c,
+            None => return err!(InvalidArtifactId, "'{}' is not a valid Cargo artifact identifier", artifact_id),
+        };
+
+        let name = &captures[1];
+        let version = &captures[2];
+        let registry = captures.get(4).map(|m| m.as_str());
+
+        // .crate files are in 'registry/cache/<index>/', unpacked sources in 'registry/src/<index>/'
+        let (folder, file_name) = if self.unpacked {
+            ("src", format!("{}-{}", name, version))
+        }
+        else {
+            ("cache", format!("{}-{}.crate", name, version))
+        };
+        let registries_folder = self.root.join("registry").join(folder);
+
+        let mut candidates = Vec::new();
+        for entry in io_guarded!(fs::read_dir(&registries_folder), ArtifactNotFound, "Cargo registry folder {:?} not found", &registries_folder) {
+            let entry = io_guarded!(entry, ArtifactFolderReadError, "Cannot read Cargo registry folder {:?}", &registries_folder);
+            let index_name = entry.file_name().to_string_lossy().to_string();
+
+            // index folders are named '<registry>-<hash of the index URL>'
+            if let Some(registry) = registry {
+                if index_name != registry && !index_name.starts_with(&format!("{}-", registry)) {
+                    continue;
+                }
+            }
+
+            let candidate = entry.path().join(&file_name);
+            if candidate.exists() {
+                candidates.push(candidate);
+            }
+        }
+
+        // a crate is usually in several index folders for the same registry, e.g. crates.io's git
+        //  and sparse index after switching to the sparse protocol. That is only ambiguous if the
+        //  copies differ.
+        candidates.sort();
+        if candidates.is_empty() {
+            return err!(ArtifactNotFound, "crate {} not found in {:?}", artifact_id, &registries_folder);
+        }
+        let first_hash = self.hash(&candidates[0])?;
+        for candidate in &candidates[1..] {
+            if self.hash(candidate)? != first_hash {
+                return err!(InvalidArtifactId, "crate {} differs between registries ({:?}) - please add the registry as in 'name:version:registry'", artifact_id, candidates);
+            }
+        }
+        if candidates.len() > 1 {
+            debug!("crate {} is identical in {:?}", artifact_id, candidates);
+        }
+        Ok(candidates.remove(0))
+    }
+
+    fn hash(&self, path: &Path) -> Result<Vec<u8>, TrustChainError> {
+        let mut hasher = Hasher::new(vec!(HashAlgorithm::Sha256.context()));
+        if self.unpacked {
+            hash_folder(&mut hasher, path)?;
+        }
+        else {
+            hash_file(&mut hasher, path)?;
+        }
+        Ok(hasher.finish().remove(0))
+    }
+}
+
+/// npm packages, identified as 'name@version' or '@scope/name@version'. Package tarballs are
+///  looked up in npm's content-addressable cache ('_cacache'), unpacked packages in a
+///  'node_modules' folder.
+pub struct NpmRepository {
+    root: PathBuf,
+    unpacked: bool,
+}
+
+/// the registry npm uses by default - tarballs from other registries are found by scanning the cache index
+const NPM_DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+
+/// An entry in a bucket file of the cache index, cf. https://github.com/npm/cacache
+#[derive(Deserialize)]
+struct NpmCacheIndexEntry {
+    key: String,
+    integrity: Option<String>,
+}
+
+impl NpmRepository {
+    fn new(root: PathBuf, unpacked: bool) -> NpmRepository {
+        NpmRepository {
+            root,
+            unpacked,
+        }
+    }
+
+    /// splits an id into name and version, taking into account that scoped names start with '@'
+    fn parse_id(artifact_id: &str) -> Result<(&str, &str), TrustChainError> {
+        match artifact_id.rfind('@') {
+            Some(idx) if idx > 0 && idx < artifact_id.len() - 1 => Ok((&artifact_id[..idx], &artifact_id[idx+1..])),
+            _ => err!(InvalidArtifactId, "'{}' is not a valid npm artifact identifier", artifact_id),
+        }
+    }
+
+    fn id_to_path(&self, artifact_id: &str) -> Result<PathBuf, TrustChainError> {
+        let (name, version) = NpmRepository::parse_id(artifact_id)?;
+
+        if self.unpacked {
+            self.package_folder(name, version)
+        }
+        else {
+            match self.cached_tarball(name, version)? {
+                Some(path) => Ok(path),
+                None => err!(ArtifactNotFound, "npm package {} not found in the cache {:?}", artifact_id, &self.root),
+            }
+        }
+    }
+
+    fn package_folder(&self, name: &str, version: &str) -> Result<PathBuf, TrustChainError> {
+        let mut folder = self.root.clone();
+        for segment in name.split('/') {
+            folder.push(segment);
+        }
+
+        let package_json_path = folder.join("package.json");
+        let package_json = io_guarded!(fs::read_to_string(&package_json_path), ArtifactNotFound, "npm package {} not found at {:?}", name, &folder);
+        let package_json: serde_json::Value = io_guarded!(serde_json::from_str(&package_json), ArtifactReadError, "error parsing {:?}", &package_json_path);
+
+        match package_json.get("version").and_then(|v| v.as_str()) {
+            Some(v) if v == version => Ok(folder),
+            Some(v) => err!(ArtifactNotFound, "npm package {} in {:?} has version {} rather than {}", name, &folder, v, version),
+            None => err!(ArtifactReadError, "{:?} contains no version", &package_json_path),
+        }
+    }
+
+    fn cached_tarball(&self, name: &str, version: &str) -> Result<Option<PathBuf>, TrustChainError> {
+        // the tarball's URL is '<registry>/<name>/-/<name without scope>-<version>.tgz'
+        let unscoped_name = name.rsplit('/').next().unwrap_or(name);
+        let url_path = format!("{}/-/{}-{}.tgz", name, unscoped_name, version);
+
+        let cache_folder = self.root.join("_cacache");
+        let default_key = format!("make-fetch-happen:request-cache:{}{}", NPM_DEFAULT_REGISTRY, url_path);
+
+        let integrity = match self.find_in_bucket(&cache_folder, &self.index_bucket(&cache_folder, &default_key), |key| key == default_key)? {
+            Some(integrity) => Some(integrity),
+            None => {
+                debug!("{} not found for the default registry, scanning the cache index", url_path);
+                let suffix = format!("/{}", url_path);
+                self.scan_index(&cache_folder, &suffix)?
+            }
+        };
+
+        match integrity {
+            Some(integrity) => Ok(Some(npm_content_path(&cache_folder, &integrity)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// bucket files are stored at 'index-v5/<sha256 of the key, split into 2/2/rest characters>'
+    fn index_bucket(&self, cache_folder: &Path, key: &str) -> PathBuf {
+        let hash = to_hex_string(digest(&SHA256, key.as_bytes()).as_ref());
+        cache_folder.join("index-v5").join(&hash[..2]).join(&hash[2..4]).join(&hash[4..])
+    }
+
+    /// Returns the integrity of the most recent entry with a matching key in a bucket file. Each
+    ///  line in a bucket is '<sha1 of the JSON>\t<JSON entry>'; entries without integrity mark
+    ///  deletions.
+    fn find_in_bucket<F>(&self, cache_folder: &Path, bucket: &Path, key_matches: F) -> Result<Option<String>, TrustChainError> where F: Fn(&str) -> bool {
+        if !bucket.is_file() {
+            return Ok(None);
+        }
+
+        let content = io_guarded!(fs::read_to_string(bucket), ArtifactReadError, "error reading npm cache index {:?} in {:?}", bucket, cache_folder);
+        let mut result = None;
+        for line in content.lines() {
+            let json = match line.split_once('\t') {
+                Some((_, json)) => json,
+                None => continue,
+            };
+            match serde_json::from_str::<NpmCacheIndexEntry>(json) {
+                Ok(entry) if key_matches(&entry.key) => result = entry.integrity,
+                Ok(_) => {},
+                Err(e) => warn!("skipping invalid entry in npm cache index {:?}: {}", bucket, e),
+            }
+        }
+        Ok(result)
+    }
+
+    fn scan_index(&self, cache_folder: &Path, url_suffix: &str) -> Result<Option<String>, TrustChainError> {
+        let index_folder = cache_folder.join("index-v5");
+        if !index_folder.is_dir() {
+            return Ok(None);
+        }
+
+        let mut folders = vec!(index_folder);
+        while let Some(folder) = folders.pop() {
+            for entry in io_guarded!(fs::read_dir(&folder), ArtifactFolderReadError, "Cannot read npm cache index folder {:?}", &folder) {
+                let entry = io_guarded!(entry, ArtifactFolderReadError, "Cannot read npm cache index folder {:?}", &folder);
+                let path = entry.path();
+                if path.is_dir() {
+                    folders.push(path);
+                }
+                else if let Some(integrity) = self.find_in_bucket(cache_folder, &path, |key| key.starts_with("make-fetch-happen:request-cache:") && key.ends_with(url_suffix))? {
+                    return Ok(Some(integrity));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Content is stored at 'content-v2/<algorithm>/<hex digest, split into 2/2/rest characters>'.
+///  The integrity is a Subresource Integrity string, e.g. 'sha512-<base64 digest>'.
+fn npm_content_path(cache_folder: &Path, integrity: &str) -> Result<PathBuf, TrustChainError> {
+    // an integrity string can contain several hashes - any of them addresses the content
+    let first = integrity.split_whitespace().next().unwrap_or_default();
+    let (algorithm, base64_digest) = match first.split_once('-') {
+        Some(x) => x,
+        None => return err!(ArtifactReadError, "invalid integrity {:?} in the npm cache", integrity),
+    };
+    // the algorithm becomes part of the path, so only known ones are accepted
+    let digest_len = match algorithm {
+        "sha1" => 20,
+        "sha256" => 32,
+        "sha512" => 64,
+        _ => return err!(ArtifactReadError, "unsupported algorithm in integrity {:?} in the npm cache", integrity),
+    };
+    let digest = io_guarded!(base64::decode(base64_digest), ArtifactReadError, "invalid integrity {:?} in the npm cache", integrity);
+    if digest.len() != digest_len {
+        return err!(ArtifactReadError, "invalid digest length in integrity {:?} in the npm cache", integrity);
+    }
+    let hex = to_hex_string(&digest);
+
+    Ok(cache_folder.join("content-v2").join(algorithm).join(&hex[..2]).join(&hex[2..4]).join(&hex[4..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const CRATES_IO: &str = "index.crates.io-6f17d22bba15001f";
+    const GITHUB: &str = "github.com-1ecc6299db9ec823";
+    const OTHER: &str = "my-registry-0123456789abcdef";
+
+    /// A Cargo home folder with the crates 'a' and 'b' in the index folders for crates.io's
+    ///  sparse and git index, and a different 'b' and 'c' for another registry
+    fn cargo_home() -> PathBuf {
+        let root = temp_folder();
+        let write = |index: &str, name: &str, content: &str| {
+            let cache = root.join("registry").join("cache").join(index);
+            fs::create_dir_all(&cache).unwrap();
+            fs::write(cache.join(format!("{}.crate", name)), content).unwrap();
+
+            let src = root.join("registry").join("src").join(index).join(name);
+            fs::create_dir_all(src.join("src")).unwrap();
+            fs::write(src.join("Cargo.toml"), content).unwrap();
+            fs::write(src.join("src").join("lib.rs"), "").unwrap();
+        };
+        for index in [CRATES_IO, GITHUB] {
+            write(index, "a-1.0.0", "a");
+            write(index, "b-1.0.0", "b");
+        }
+        write(OTHER, "b-1.0.0", "other b");
+        write(OTHER, "c-0.1.0-alpha", "c");
+        root
+    }
+
+    fn temp_folder() -> PathBuf {
+        std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()))
+    }
+
+    fn sha256(content: &[u8]) -> Vec<u8> {
+        ring::digest::digest(&SHA256, content).as_ref().to_vec()
+    }
+
+    #[test]
+    fn test_cargo_ids() {
+        let root = cargo_home();
+        let repo = CargoRepository::new(root.clone(), false);
+        let cache = root.join("registry").join("cache");
+
+        assert_eq!(repo.id_to_path("c:0.1.0-alpha").unwrap(), cache.join(OTHER).join("c-0.1.0-alpha.crate"));
+        assert_eq!(repo.id_to_path("b:1.0.0:my-registry").unwrap(), cache.join(OTHER).join("b-1.0.0.crate"));
+        assert_eq!(repo.id_to_path(&format!("b:1.0.0:{}", OTHER)).unwrap(), cache.join(OTHER).join("b-1.0.0.crate"));
+        assert_eq!(repo.id_to_path("b:1.0.0:github.com").unwrap(), cache.join(GITHUB).join("b-1.0.0.crate"));
+
+        for id in ["a", "a:", "a:1.0.0:index:x", ""] {
+            let e = repo.id_to_path(id).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{} {:?}", id, e);
+        }
+        for id in ["a:2.0.0", "c:0.1.0-alpha:index.crates.io", "a:1.0.0:my"] {
+            let e = repo.id_to_path(id).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::ArtifactNotFound), "{} {:?}", id, e);
+        }
+
+        let e = CargoRepository::new(root.join("missing"), false).id_to_path("a:1.0.0").unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::ArtifactNotFound), "{:?}", e);
+    }
+
+    #[test]
+    fn test_cargo_copies() {
+        let root = cargo_home();
+
+        // identical copies for crates.io's sparse and git index
+        let repo = ArtifactRepository::new_cargo(root.clone(), false);
+        assert_eq!(repo.do_hash("a:1.0.0", HashAlgorithm::Sha256).unwrap().hash, sha256(b"a"));
+        assert_eq!(repo.do_hash("b:1.0.0:index.crates.io", HashAlgorithm::Sha256).unwrap().hash, sha256(b"b"));
+        assert_eq!(repo.do_hash("b:1.0.0:my-registry", HashAlgorithm::Sha256).unwrap().hash, sha256(b"other b"));
+
+        // different crates with the same name and version in different registries
+        let e = repo.do_hash("b:1.0.0", HashAlgorithm::Sha256).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{:?}", e);
+    }
+
+    #[test]
+    fn test_cargo_src() {
+        let root = cargo_home();
+        let repo = ArtifactRepository::new_cargo(root.clone(), true);
+
+        // unpacked crates are hashed in the tree format
+        let a = root.join("registry").join("src").join(CRATES_IO).join("a-1.0.0");
+        let id = repo.do_hash("a:1.0.0", HashAlgorithm::Sha256).unwrap();
+        assert!(id.tree);
+        assert_eq!(vec!(id), hash_path(&a, &[HashAlgorithm::Sha256]).unwrap());
+
+        let e = repo.do_hash("b:1.0.0", HashAlgorithm::Sha256).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{:?}", e);
+
+        // the hash covers file names as well as content
+        let src = root.join("registry").join("src").join(OTHER).join("c-0.1.0-alpha");
+        let before = repo.do_hash("c:0.1.0-alpha", HashAlgorithm::Sha256).unwrap().hash;
+        fs::rename(src.join("src").join("lib.rs"), src.join("src").join("main.rs")).unwrap();
+        assert_ne!(repo.do_hash("c:0.1.0-alpha", HashAlgorithm::Sha256).unwrap().hash, before);
+    }
+
+    /// Adds an entry for a tarball from the default registry to a bucket in the npm cache index
+    fn add_index_entry(root: &Path, name: &str, version: &str, integrity: Option<&str>) {
+        let repo = NpmRepository::new(root.to_path_buf(), false);
+        let unscoped_name = name.rsplit('/').next().unwrap();
+        let key = format!("make-fetch-happen:request-cache:{}{}/-/{}-{}.tgz", NPM_DEFAULT_REGISTRY, name, unscoped_name, version);
+        let bucket = repo.index_bucket(&root.join("_cacache"), &key);
+        fs::create_dir_all(bucket.parent().unwrap()).unwrap();
+
+        let json = serde_json::json!({"key": key, "integrity": integrity, "time": 1600000000000u64, "size": 1}).to_string();
+        let mut content = fs::read_to_string(&bucket).unwrap_or_default();
+        content.push_str(&format!("\n{}\t{}", to_hex_string(ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, json.as_bytes()).as_ref()), json));
+        fs::write(&bucket, content).unwrap();
+    }
+
+    /// Adds a tarball to the npm cache's content store, returning its integrity
+    fn add_content(root: &Path, content: &[u8]) -> String {
+        let integrity = format!("sha512-{}", base64::encode(ring::digest::digest(&ring::digest::SHA512, content)));
+        let path = npm_content_path(&root.join("_cacache"), &integrity).unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        integrity
+    }
+
+    #[test]
+    fn test_npm_cache() {
+        let root = temp_folder();
+        let repo = ArtifactRepository::new_npm(root.clone(), false);
+
+        let old = add_content(&root, b"old lodash");
+        let new = add_content(&root, b"new lodash");
+        add_index_entry(&root, "lodash", "4.17.21", Some(&old));
+        add_index_entry(&root, "lodash", "4.17.21", Some(&new));
+        assert_eq!(repo.do_hash("lodash@4.17.21", HashAlgorithm::Sha256).unwrap().hash, sha256(b"new lodash"));
+
+        let scoped = add_content(&root, b"scoped");
+        add_index_entry(&root, "@types/node", "20.1.0", Some(&scoped));
+        assert_eq!(repo.do_hash("@types/node@20.1.0", HashAlgorithm::Sha256).unwrap().hash, sha256(b"scoped"));
+
+        // an entry without integrity deletes the previous ones
+        add_index_entry(&root, "@types/node", "20.1.0", None);
+        let e = repo.do_hash("@types/node@20.1.0", HashAlgorithm::Sha256).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::ArtifactNotFound), "{:?}", e);
+
+        let e = repo.do_hash("lodash@4.17.20", HashAlgorithm::Sha256).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::ArtifactNotFound), "{:?}", e);
+        for id in ["lodash", "lodash@", "@types/node"] {
+            let e = repo.do_hash(id, HashAlgorithm::Sha256).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{} {:?}", id, e);
+        }
+    }
+
+    #[test]
+    fn test_npm_modules() {
+        let root = temp_folder();
+        for (folder, version) in [("lodash", "4.17.21"), ("@types/node", "20.1.0")] {
+            let folder = root.join(folder);
+            fs::create_dir_all(&folder).unwrap();
+            fs::write(folder.join("package.json"), format!(r#"{{"version":"{}"}}"#, version)).unwrap();
+        }
+        let repo = NpmRepository::new(root.clone(), true);
+
+        assert_eq!(repo.id_to_path("lodash@4.17.21").unwrap(), root.join("lodash"));
+        assert_eq!(repo.id_to_path("@types/node@20.1.0").unwrap(), root.join("@types").join("node"));
+        for id in ["lodash@4.17.20", "underscore@1.0.0"] {
+            let e = repo.id_to_path(id).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::ArtifactNotFound), "{} {:?}", id, e);
+        }
+
+        let ids = ArtifactRepository::new_npm(root.clone(), true).do_hash_all("lodash@4.17.21").unwrap();
+        assert!(ids.iter().all(|id| id.tree));
+        assert_eq!(ids, hash_path(&root.join("lodash"), &HashAlgorithm::ALL).unwrap());
+    }
+
+    #[test]
+    fn test_artifact_id() {
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let id: ArtifactId = sha256.parse().unwrap();
+        assert_eq!(id.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(id.to_string(), format!("sha256:{}", sha256));
+        assert_eq!(format!("sha256:{}", sha256.to_uppercase()).parse::<ArtifactId>().unwrap(), id);
+
+        let blake3: ArtifactId = format!("blake3:{}", sha256).parse().unwrap();
+        assert_eq!(blake3.algorithm, HashAlgorithm::Blake3);
+        assert_eq!(blake3.hash, id.hash);
+
+        let tree: ArtifactId = format!("tree-sha256:{}", sha256).parse().unwrap();
+        assert!(tree.tree && !id.tree);
+        assert_eq!(tree.hash, id.hash);
+        assert_eq!(tree.to_string(), format!("tree-sha256:{}", sha256));
+        assert_ne!(tree, id);
+
+        for invalid in ["", "sha256:", "md5:900150983cd24fb0d6963f7d28e17f72", "sha512:ba7816bf", "SHA256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015a", "+a7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                        "tree-ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad", "tree-tree-sha256:ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"] {
+            assert!(invalid.parse::<ArtifactId>().is_err(), "{}", invalid);
+        }
+
+        assert_eq!("sha512".parse::<HashAlgorithm>().unwrap(), HashAlgorithm::Sha512);
+        assert!("sha1".parse::<HashAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let path = temp_folder().join("abc");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "abc").unwrap();
+
+        let mut hasher = Hasher::new(HashAlgorithm::ALL.iter().map(HashAlgorithm::context).collect());
+        hash_file(&mut hasher, &path).unwrap();
+        let hashes: Vec<String> = hasher.finish().iter().map(|h| to_hex_string(h)).collect();
+        assert_eq!(hashes, vec!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+        ));
+
+        fs::write(&path, "").unwrap();
+        let mut hasher = Hasher::new(vec!(HashAlgorithm::Blake3.context()));
+        hash_file(&mut hasher, &path).unwrap();
+        assert_eq!(to_hex_string(&hasher.finish()[0]), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+    }
+
+    #[test]
+    fn test_npm_content_path() {
+        let path = npm_content_path(Path::new("/cache"), "sha1-AAECAwQFBgcICQoLDA0ODxAREhM= sha512-xyz").unwrap();
+        assert_eq!(path, Path::new("/cache/content-v2/sha1/00/01/02030405060708090a0b0c0d0e0f10111213"));
+
+        for integrity in &["sha512-", "sha512-AA==", "sha1-AAECAwQFBgcICQoLDA0ODxAREhM=x", "../x-AAECAwQFBgcICQoLDA0ODxAREhM=", "sha1", ""] {
+            assert!(npm_content_path(Path::new("/cache"), integrity).is_err(), "{}", integrity);
+        }
+    }
+
+    #[test]
+    fn test_maven_coordinates() {
+        let maven = MavenRepository::new(PathBuf::from("/m2"));
+        let coordinates = maven.parse_id("org.foo:bar:test-jar:1.0").unwrap();
+        assert_eq!(coordinates, MavenCoordinates {
+            group_id: "org.foo".to_string(),
+            artifact_id: "bar".to_string(),
+            packaging: "test-jar".to_string(),
+            classifier: None,
+            version: "1.0".to_string(),
+        });
+        assert_eq!(coordinates.to_string(), "org.foo:bar:test-jar:1.0");
+        assert_eq!(maven.parse_id("org.foo:bar:1.0").unwrap().to_string(), "org.foo:bar:jar:1.0");
+        assert_eq!(maven.parse_id("org.foo:bar:jar:linux-x86_64:1.0").unwrap().classifier.as_deref(), Some("linux-x86_64"));
+        for id in ["org.foo:bar", "org.foo::1.0", "org.foo:bar:jar:linux:x86:1.0", "org.foo:bar:1.0:"] {
+            assert!(matches!(maven.parse_id(id).unwrap_err().kind, TrustChainErrorKind::InvalidArtifactId), "{}", id);
+        }
+
+        for (id, path) in [
+            ("org.foo:bar:1.0", "/m2/org/foo/bar/1.0/bar-1.0.jar"),
+            ("org.foo:bar:pom:1.0", "/m2/org/foo/bar/1.0/bar-1.0.pom"),
+            ("org.foo:bar:maven-plugin:1.0", "/m2/org/foo/bar/1.0/bar-1.0.jar"),
+            ("org.foo:bar:test-jar:1.0", "/m2/org/foo/bar/1.0/bar-1.0-tests.jar"),
+            ("org.foo:bar:java-source:1.0", "/m2/org/foo/bar/1.0/bar-1.0-sources.jar"),
+            ("org.foo:bar:jar:sources:1.0", "/m2/org/foo/bar/1.0/bar-1.0-sources.jar"),
+            ("org.foo:bar:zip:dist:1.0", "/m2/org/foo/bar/1.0/bar-1.0-dist.zip"),
+            ("org.foo:bar:1.0-SNAPSHOT", "/m2/org/foo/bar/1.0-SNAPSHOT/bar-1.0-SNAPSHOT.jar"),
+        ] {
+            assert_eq!(maven.id_to_path(id).unwrap(), PathBuf::from(path), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_maven_snapshot() {
+        let root = temp_folder();
+        let version_folder = root.join("org/foo/bar/1.0-SNAPSHOT");
+        fs::create_dir_all(&version_folder).unwrap();
+        let metadata = |versions: &[(Option<&str>, &str, &str)]| {
+            let versions: Vec<String> = versions.iter().map(|(classifier, extension, value)| format!(
+                "<snapshotVersion>{}<extension>{}</extension><value>{}</value></snapshotVersion>",
+                classifier.map(|c| format!("<classifier>{}</classifier>", c)).unwrap_or_default(), extension, value)).collect();
+            format!("<metadata><versioning><snapshotVersions>{}</snapshotVersions></versioning></metadata>", versions.join(""))
+        };
+        fs::write(version_folder.join("maven-metadata-central.xml"), metadata(&[
+            (None, "jar", "1.0-20200101.123456-1"),
+            (Some("sources"), "jar", "1.0-20200101.123456-2"),
+            (None, "pom", "1.0-20200101.123456-3"),
+        ])).unwrap();
+
+        let maven = MavenRepository::new(root.clone());
+        let path = |id: &str| maven.id_to_path(id).unwrap().strip_prefix(&version_folder).unwrap().to_string_lossy().to_string();
+        assert_eq!(path("org.foo:bar:1.0-SNAPSHOT"), "bar-1.0-20200101.123456-1.jar");
+        assert_eq!(path("org.foo:bar:java-source:1.0-SNAPSHOT"), "bar-1.0-20200101.123456-2-sources.jar");
+        assert_eq!(path("org.foo:bar:pom:1.0-SNAPSHOT"), "bar-1.0-20200101.123456-3.pom");
+        assert_eq!(path("org.foo:bar:jar:javadoc:1.0-SNAPSHOT"), "bar-1.0-SNAPSHOT-javadoc.jar");
+
+        // locally installed snapshots take precedence
+        fs::write(version_folder.join("maven-metadata-local.xml"), metadata(&[(None, "jar", "1.0-SNAPSHOT")])).unwrap();
+        assert_eq!(path("org.foo:bar:1.0-SNAPSHOT"), "bar-1.0-SNAPSHOT.jar");
+        assert_eq!(path("org.foo:bar:pom:1.0-SNAPSHOT"), "bar-1.0-20200101.123456-3.pom");
+
+        fs::write(version_folder.join("maven-metadata-local.xml"), "<metadata>").unwrap();
+        assert!(matches!(maven.id_to_path("org.foo:bar:1.0-SNAPSHOT").unwrap_err().kind, TrustChainErrorKind::ArtifactReadError));
+    }
+
+    /// the expected hash is calculated by an independent implementation of the documented format
+    #[cfg(unix)]
+    #[test]
+    fn test_tree_hash() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let root = temp_folder().join("tree");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("README"), "hello\n").unwrap();
+        fs::set_permissions(root.join("README"), fs::Permissions::from_mode(0o644)).unwrap();
+        fs::write(root.join("src/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(root.join("src/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("../README", root.join("src/link")).unwrap();
+
+        let ids = hash_path(&root, &[HashAlgorithm::Sha256]).unwrap();
+        assert_eq!(ids[0].to_string(), "tree-sha256:1994edac7f1653db662d4ca85a1e8ec07275c0e92ac46a7a2ceac9e6abf93344");
+        assert!(!hash_path(&root.join("README"), &[HashAlgorithm::Sha256]).unwrap()[0].tree);
+
+        // only the execute bits are part of the hash
+        fs::set_permissions(root.join("README"), fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(hash_path(&root, &[HashAlgorithm::Sha256]).unwrap(), ids);
+
+        fs::set_permissions(root.join("README"), fs::Permissions::from_mode(0o744)).unwrap();
+        assert_ne!(hash_path(&root, &[HashAlgorithm::Sha256]).unwrap(), ids);
+    }
+
+    /// An OCI layout with an image 'example.com/app:1.0' of a config and two layers, returning the
+    ///  layout and the digests of the manifest and the layers
+    fn oci_layout() -> (PathBuf, String, Vec<String>) {
+        let root = temp_folder();
+        fs::create_dir_all(root.join("blobs/sha256")).unwrap();
+        let write_blob = |content: &str| {
+            let digest = hash_with(&[HashAlgorithm::Sha256], |hasher| { hasher.update(content.as_bytes()); Ok(false) }).unwrap().remove(0);
+            fs::write(root.join(oci_blob_name(&digest)), content).unwrap();
+            digest.to_string()
+        };
+
+        let config = write_blob("{}");
+        let layers = vec!(write_blob("layer 0"), write_blob("layer 1"));
+        let manifest = write_blob(&format!(r#"{{"schemaVersion": 2, "config": {{"digest": "{}"}}, "layers": [{{"digest": "{}"}}, {{"digest": "{}"}}]}}"#,
+                                           config, layers[0], layers[1]));
+        fs::write(root.join("index.json"), format!(r#"{{"schemaVersion": 2, "manifests": [{{"digest": "{}", "annotations": {{"org.opencontainers.image.ref.name": "example.com/app:1.0"}}}}]}}"#, manifest)).unwrap();
+        (root, manifest, layers)
+    }
+
+    fn oci_hash(root: &Path, artifact_id: &str) -> Result<String, TrustChainError> {
+        let repository = OciRepository { root: root.to_path_buf() };
+        Ok(hash_with(&[HashAlgorithm::Sha256], |hasher| repository.hash_artifact(artifact_id, hasher).map(|_| false))?.remove(0).to_string())
+    }
+
+    #[test]
+    fn test_complete_docker_reference() {
+        assert_eq!(complete_docker_reference("alpine"), "docker.io/library/alpine:latest");
+        assert_eq!(complete_docker_reference("alpine:3.19"), "docker.io/library/alpine:3.19");
+        assert_eq!(complete_docker_reference("foo/bar"), "docker.io/foo/bar:latest");
+        assert_eq!(complete_docker_reference("ghcr.io/foo/bar:1"), "ghcr.io/foo/bar:1");
+        assert_eq!(complete_docker_reference("localhost:5000/bar"), "localhost:5000/bar:latest");
+        assert_eq!(complete_docker_reference("localhost/bar"), "localhost/bar:latest");
+    }
+
+    #[test]
+    fn test_oci_repository() {
+        let (root, manifest, layers) = oci_layout();
+
+        // an image's hash is its digest
+        for image in ["example.com/app:1.0", &format!("example.com/app@{}", manifest), &manifest] {
+            assert_eq!(oci_hash(&root, image).unwrap(), manifest, "{}", image);
+        }
+        assert_eq!(oci_hash(&root, "example.com/app:1.0#1").unwrap(), layers[1]);
+        assert_eq!(oci_hash(&root, &format!("example.com/app:1.0#{}", layers[0])).unwrap(), layers[0]);
+
+        assert!(matches!(oci_hash(&root, "example.com/app:2.0").unwrap_err().kind, TrustChainErrorKind::ArtifactNotFound));
+        assert!(matches!(oci_hash(&root, "example.com/app:1.0#2").unwrap_err().kind, TrustChainErrorKind::ArtifactNotFound));
+        assert!(oci_hash(&root, &manifest[7..]).is_err());
+
+        // a tarball of the layout works the same
+        let tarball = temp_folder().join("image.tar");
+        fs::create_dir_all(tarball.parent().unwrap()).unwrap();
+        let mut builder = tar::Builder::new(File::create(&tarball).unwrap());
+        builder.append_dir_all(".", &root).unwrap();
+        builder.finish().unwrap();
+        assert_eq!(oci_hash(&tarball, "example.com/app:1.0").unwrap(), manifest);
+        assert_eq!(oci_hash(&tarball, "example.com/app:1.0#0").unwrap(), layers[0]);
+
+        // the image's content must match the manifest
+        let layer_path = root.join(oci_blob_name(&layers[1].parse().unwrap()));
+        fs::write(&layer_path, "tampered").unwrap();
+        for image in ["example.com/app:1.0", "example.com/app:1.0#1"] {
+            assert!(matches!(oci_hash(&root, image).unwrap_err().kind, TrustChainErrorKind::ArtifactReadError), "{}", image);
+        }
+        assert_eq!(oci_hash(&root, "example.com/app:1.0#0").unwrap(), layers[0]);
+    }
+
+    #[test]
+    fn test_python_distribution_name() {
+        assert_eq!(python_distribution_name("requests-2.31.0-py3-none-any.whl"), Some(("requests", "2.31.0")));
+        assert_eq!(python_distribution_name("numpy-1.26.0-1-cp311-cp311-manylinux_2_17_x86_64.whl"), Some(("numpy", "1.26.0")));
+        assert_eq!(python_distribution_name("my-package-1.0.tar.gz"), Some(("my-package", "1.0")));
+        assert_eq!(python_distribution_name("foo-1.0.zip"), Some(("foo", "1.0")));
+        assert_eq!(python_distribution_name("foo-1.0-py3.whl"), None);
+        assert_eq!(python_distribution_name("foo.tar.gz"), None);
+        assert_eq!(python_distribution_name("-1.0.tar.gz"), None);
+        assert_eq!(python_distribution_name("foo-1.0.jar"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_python_distributions_symlink_cycle() {
+        let root = temp_folder();
+        fs::create_dir_all(root.join("simple/foo")).unwrap();
+        fs::write(root.join("simple/foo/Foo-1.0-py3-none-any.whl"), "").unwrap();
+        fs::write(root.join("simple/foo/foo-1.0.tar.gz"), "").unwrap();
+        fs::write(root.join("simple/foo/foo-1.1.tar.gz"), "").unwrap();
+        std::os::unix::fs::symlink("..", root.join("simple/foo/cycle")).unwrap();
+        std::os::unix::fs::symlink("foo/foo-1.0.tar.gz", root.join("simple/foo-1.0.zip")).unwrap();
+
+        let distributions = python_distributions(&root, "FOO", "1.0").unwrap();
+        let names: Vec<_> = distributions.iter().map(|d| d.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!("Foo-1.0-py3-none-any.whl", "foo-1.0.tar.gz", "foo-1.0.zip"));
+    }
 }
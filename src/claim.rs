@@ -4,78 +4,143 @@ use std::time::SystemTime;
 
 use log::*;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use uuid::Uuid;
 
-use crate::artifacts::ArtifactId;
+use crate::artifacts::{ArtifactId, HashAlgorithm};
+use crate::claim_kind::ClaimKind;
 use crate::util::to_hex_string;
 use std::sync::Arc;
-use ring::digest::{Digest, Context, SHA256};
 use std::fs::File;
 use crate::err::TrustChainError;
-use crate::gpg::{Gpg, SignedFilePath, PublicKey};
+use crate::signing::{PublicKey, SignedFilePath, Signer, Verification, Verifier};
+use crate::trust::normalize_fingerprint;
 
 pub enum AuthenticatedClaim {
     Positive(PositiveClaimData),
-    Revocation(CommonClaimData),
+    Revocation(RevocationClaimData),
 }
 
-pub struct ClaimKind {
-    claim_kind: String,
-}
-impl ClaimKind {
-    fn new(kind: &str) -> ClaimKind {
-        ClaimKind {
-            claim_kind: kind.to_string()
+impl AuthenticatedClaim {
+    pub fn common_data(&self) -> &CommonClaimData {
+        match self {
+            AuthenticatedClaim::Positive(data) => &data.common_data,
+            AuthenticatedClaim::Revocation(data) => &data.common_data,
         }
     }
 }
 
 pub struct CommonClaimData {
-    id: Uuid,
-    uid: String,
-    artifact_id: ArtifactId,
-    comment: Option<String>,
-    timestamp: SystemTime,
+    pub id: Uuid,
+    pub uid: String,
+    pub signer: PublicKey,
+    pub artifact_id: ArtifactId,
+    /// the artifact's identifier in its repository, as stated by the claim's signer
+    pub artifact_name: String,
+    pub comment: Option<String>,
+    pub timestamp: SystemTime,
 }
 
 pub struct PositiveClaimData {
-    common_data: CommonClaimData,
-    kind: ClaimKind,
+    pub common_data: CommonClaimData,
+    pub kind: ClaimKind,
+    pub value: Option<String>,
 }
 
+pub struct RevocationClaimData {
+    pub common_data: CommonClaimData,
+    pub revoked_claim_id: Uuid,
+}
+
+/// Looks up the revocation of a positive claim among a list of claims for the same artifact.
+///  Only revocations signed by the same key as the claim itself are taken into account.
+pub fn find_revocation<'a>(claim: &PositiveClaimData, all_claims: &'a [Arc<AuthenticatedClaim>]) -> Option<&'a RevocationClaimData> {
+    all_claims.iter()
+        .filter_map(|c| match c.as_ref() {
+            AuthenticatedClaim::Revocation(r) => Some(r),
+            _ => None,
+        })
+        .find(|r| r.revoked_claim_id == claim.common_data.id && r.common_data.signer.fingerprint == claim.common_data.signer.fingerprint)
+}
+
+
+/// The version of the on-disk claim format written by this code. Claim files without a format
+///  version were written before the format was versioned, see `LegacyClaim`.
+const CLAIM_FORMAT_VERSION: u64 = 1;
 
 #[derive(Serialize, Deserialize)]
 struct PersistentClaim {
+    format_version: u64,
     id: String,
     uid: String,
-    sig: String,
     artifact_id: String,
+    artifact_hash: String,
+    /// claims written before artifact ids were tagged with their algorithm do not have this, and
+    ///  their hashes are SHA-256
+    #[serde(default = "legacy_hash_algorithm")]
+    artifact_hash_algorithm: HashAlgorithm,
+    /// whether the artifact is a folder hashed in the tree format, missing in claims written before
+    ///  tree hashes were tagged
+    #[serde(default)]
+    artifact_tree_hash: bool,
     comment: Option<String>,
     timestamp: SystemTime,
     specifics: PersistentClaimSpecifics,
 }
 
+fn legacy_hash_algorithm() -> HashAlgorithm {
+    HashAlgorithm::Sha256
+}
+
 impl PersistentClaim {
-    fn into_authenticated_claim(self) -> AuthenticatedClaim {
+    fn new(uid: String, artifact_id: &str, artifact_hash: &ArtifactId, comment: Option<&str>, specifics: PersistentClaimSpecifics) -> PersistentClaim {
+        PersistentClaim {
+            format_version: CLAIM_FORMAT_VERSION,
+            id: uuid::Uuid::new_v4().to_hyphenated().to_string(),
+            uid,
+            artifact_id: artifact_id.to_string(),
+            artifact_hash: to_hex_string(&artifact_hash.hash),
+            artifact_hash_algorithm: artifact_hash.algorithm,
+            artifact_tree_hash: artifact_hash.tree,
+            comment: comment.map(str::to_string),
+            timestamp: SystemTime::now(),
+            specifics,
+        }
+    }
+
+    fn artifact_hash(&self) -> Result<ArtifactId, TrustChainError> {
+        match ArtifactId::from_hex(self.artifact_hash_algorithm, &self.artifact_hash) {
+            Ok(artifact_hash) => Ok(ArtifactId { tree: self.artifact_tree_hash, ..artifact_hash }),
+            Err(e) => err!(Claims, "invalid artifact hash in claim {}: {}", &self.id, e.description),
+        }
+    }
+
+    fn into_authenticated_claim(self, signer: PublicKey) -> Result<AuthenticatedClaim, TrustChainError> {
+        let artifact_id = self.artifact_hash()?;
+
         let common = CommonClaimData {
-            id: Uuid::parse_str(&self.id).unwrap(), //TODO error handling
+            id: io_guarded!(Uuid::parse_str(&self.id), Claims, "invalid claim id {:?}", &self.id),
             uid: self.uid,
-            artifact_id: ArtifactId {
-                hash: Context::new(&SHA256).finish(), //TODO
-            },
+            signer,
+            artifact_id,
+            artifact_name: self.artifact_id,
             comment: self.comment,
             timestamp: self.timestamp
         };
 
-        match self.specifics {
+        Ok(match self.specifics {
             PersistentClaimSpecifics::Positive(data) =>
                 AuthenticatedClaim::Positive(PositiveClaimData {
                     common_data: common,
-                    kind: ClaimKind { claim_kind: data.claim_kind }
+                    kind: ClaimKind::new(&data.claim_kind),
+                    value: data.claim_value,
                 }),
-            PersistentClaimSpecifics::Revocation(_) =>
-                AuthenticatedClaim::Revocation(common)
-        }
+            PersistentClaimSpecifics::Revocation(data) =>
+                AuthenticatedClaim::Revocation(RevocationClaimData {
+                    revoked_claim_id: io_guarded!(Uuid::parse_str(&data.claim_id), Claims, "invalid revoked claim id {:?}", &data.claim_id),
+                    common_data: common,
+                })
+        })
     }
 }
 
@@ -88,34 +153,127 @@ enum PersistentClaimSpecifics {
 #[derive(Serialize, Deserialize)]
 struct PersistentPositiveClaimData {
     claim_kind: String,
+    claim_value: Option<String>,
 }
 #[derive(Serialize, Deserialize)]
 struct PersistentRevocationData {
+    claim_id: String,
 }
 
+/// Claims as they were written before the claim format was versioned. They are still accepted
+///  when reading, and the `migrate` command rewrites them in the current format.
+#[derive(Deserialize)]
+struct LegacyClaim {
+    id: String,
+    artifact_id: String,
+    artifact_hash: String,
+    claim_key: String,
+    claim_value: Option<String>,
+}
 
+impl LegacyClaim {
+    /// Legacy claims contain neither a uid nor a timestamp, so these must be provided from the
+    ///  outside
+    fn into_persistent_claim(self, uid: String, file_timestamp: SystemTime) -> PersistentClaim {
+        PersistentClaim {
+            format_version: CLAIM_FORMAT_VERSION,
+            id: self.id,
+            uid,
+            artifact_id: self.artifact_id,
+            artifact_hash: self.artifact_hash,
+            artifact_hash_algorithm: HashAlgorithm::Sha256,
+            artifact_tree_hash: false,
+            comment: None,
+            timestamp: file_timestamp,
+            specifics: PersistentClaimSpecifics::Positive(PersistentPositiveClaimData { claim_kind: self.claim_key, claim_value: self.claim_value }),
+        }
+    }
+}
+
+/// A claim file's content, converted to the current format if necessary
+struct StoredClaim {
+    claim: PersistentClaim,
+    /// legacy claims contain no uid, so it must be taken from the signature
+    legacy: bool,
+}
+
+
+/// Claims are about an artifact hashed with a specific algorithm. Operations on existing claims
+///  take all of the artifact's ids, so that the claim is found regardless of the algorithm its
+///  signer chose.
 pub trait ClaimRegistry {
-    fn sign_claim(&self, artifact_id: &str, artifact_hash: &Digest, claim_key: &str, claim_value: Option<&str>) -> Result<String, TrustChainError>;
-    fn revoke_claim(&self, artifact_id: &str, artifact_hash: &Digest, claim_id: &str) -> Result<String, TrustChainError>;
-    fn verify_claim(&self, artifact_hash: &Digest, claim_file_name: &str) -> Result<PublicKey, TrustChainError>;
+    fn sign_claim(&self, artifact_id: &str, artifact_hash: &ArtifactId, claim_key: &str, claim_value: Option<&str>, comment: Option<&str>) -> Result<String, TrustChainError>;
+    fn revoke_claim(&self, artifact_id: &str, artifact_hashes: &[ArtifactId], claim_id: &str) -> Result<String, TrustChainError>;
+    fn verify_claim(&self, artifact_hashes: &[ArtifactId], claim_file_name: &str) -> Result<Verification, TrustChainError>;
     fn authenticated_claims_for(&self, artifact: &ArtifactId) -> Result<Box<dyn Iterator<Item=Arc<AuthenticatedClaim>>>, TrustChainError>;
+
+    /// The claims about an artifact under any of its ids
+    fn authenticated_claims_for_all(&self, artifacts: &[ArtifactId]) -> Result<Vec<Arc<AuthenticatedClaim>>, TrustChainError> {
+        let mut result = Vec::new();
+        for artifact in artifacts {
+            result.extend(self.authenticated_claims_for(artifact)?);
+        }
+        Ok(result)
+    }
+
+    /// The key that claims and revocations created by this registry are signed with
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError>;
+
+    /// All claims and revocations in the registry that were signed by the key with the given
+    ///  fingerprint, optionally restricted by the filter's criteria
+    fn claims_by_signer(&self, fingerprint: &str, filter: &ClaimFilter) -> Result<Vec<Arc<AuthenticatedClaim>>, TrustChainError>;
+
+    /// Rewrites all claims signed by the own key that are stored in a legacy format, returning
+    ///  the number of migrated files
+    fn migrate_claims(&self) -> Result<usize, TrustChainError>;
+}
+
+/// Criteria for restricting a search for claims. Revocations are never filtered out based on
+///  the claim key because they do not have one.
+#[derive(Default)]
+pub struct ClaimFilter {
+    /// the artifact's ids for all hash algorithms
+    pub artifacts: Option<Vec<ArtifactId>>,
+    pub claim_key: Option<String>,
+}
+
+impl ClaimFilter {
+    fn matches(&self, claim: &AuthenticatedClaim) -> bool {
+        match (claim, &self.claim_key) {
+            (AuthenticatedClaim::Positive(data), Some(claim_key)) => &data.kind.claim_kind == claim_key,
+            _ => true,
+        }
+    }
 }
 
 pub struct FileSystemClaimRegistry {
     root: PathBuf,
+    signer: Arc<dyn Signer>,
+    verifier: Arc<dyn Verifier>,
+    /// claim files bigger than this are ignored, and claims must not be bigger than this
+    max_claim_size: u64,
 }
 
 impl FileSystemClaimRegistry {
-    pub fn new(root: PathBuf) -> std::io::Result<FileSystemClaimRegistry> {
+    pub fn new(root: PathBuf, signer: Arc<dyn Signer>, verifier: Arc<dyn Verifier>, max_claim_size: u64) -> std::io::Result<FileSystemClaimRegistry> {
         std::fs::create_dir_all(&root)?;
-        Ok(FileSystemClaimRegistry {root})
+        Ok(FileSystemClaimRegistry {root, signer, verifier, max_claim_size})
     }
 }
 
 impl FileSystemClaimRegistry {
-    fn artifact_folder(&self, artifact_hash: &Digest, create: bool) -> Result<PathBuf, TrustChainError> {
+    /// An artifact's claims are stored in a folder named '<algorithm>-<hex digest>', except for
+    ///  SHA-256 hashes of files: their folders are named by the plain hex digest, as in registries
+    ///  written before artifact ids were tagged with their algorithm. Folders for tree hashes are
+    ///  named 'tree-<algorithm>-<hex digest>'.
+    fn artifact_folder(&self, artifact: &ArtifactId, create: bool) -> Result<PathBuf, TrustChainError> {
         //TODO hierarchy of folders
-        let result = self.root.join(to_hex_string(artifact_hash.as_ref()));
+        let folder_name = match (artifact.tree, artifact.algorithm) {
+            (true, algorithm) => format!("tree-{}-{}", algorithm, to_hex_string(&artifact.hash)),
+            (false, HashAlgorithm::Sha256) => to_hex_string(&artifact.hash),
+            (false, algorithm) => format!("{}-{}", algorithm, to_hex_string(&artifact.hash)),
+        };
+        let result = self.root.join(folder_name);
         if create {
             io_guarded!(fs::create_dir_all(&result), Claims, "error creating folder {:?}", &result);
         }
@@ -123,11 +281,28 @@ impl FileSystemClaimRegistry {
         Ok(result)
     }
 
-    fn sign_and_move_to_registry(&self, artifact_hash: &Digest, json: &str, data_file_name: &str, kind_of_file: &str) -> Result<(), TrustChainError> {
+    /// The artifact hashes for which there are folders in the registry
+    fn artifact_hashes(&self) -> Result<Vec<ArtifactId>, TrustChainError> {
+        let mut result = Vec::new();
+        for entry in io_guarded!(fs::read_dir(&self.root), Claims, "error reading registry folder {:?}", &self.root) {
+            let entry = io_guarded!(entry, Claims, "error reading registry folder {:?}", &self.root);
+            if !entry.path().is_dir() {
+                continue;
+            }
+
+            match entry.file_name().to_str().and_then(artifact_from_folder_name) {
+                Some(artifact) => result.push(artifact),
+                None => warn!("skipping unexpected folder {:?} in the registry", entry.path()),
+            }
+        }
+        Ok(result)
+    }
+
+    fn sign_and_move_to_registry(&self, artifact_hash: &ArtifactId, json: &str, data_file_name: &str, kind_of_file: &str) -> Result<(), TrustChainError> {
         let temp_path = SignedFilePath::temp(data_file_name);
         temp_path.create_data_file(json, kind_of_file)?;
 
-        Gpg::sign(kind_of_file, &temp_path)?;
+        self.signer.sign(kind_of_file, &temp_path)?;
 
         let artifact_folder = self.artifact_folder(artifact_hash, true)?;
 
@@ -140,59 +315,141 @@ impl FileSystemClaimRegistry {
 
         Ok(())
     }
+
+    /// Looks up all revocations of a given claim in an artifact folder, returning them together
+    ///  with the key they were signed with. Revocations without a valid signature or that do not
+    ///  belong in the folder are ignored.
+    fn revocations_of(&self, artifact_folder: &Path, artifact_hash: &ArtifactId, claim_id: &str) -> Result<Vec<(PersistentClaim, PublicKey)>, TrustChainError> {
+        let mut result = Vec::new();
+        if !artifact_folder.is_dir() {
+            return Ok(result);
+        }
+
+        for path in data_files(artifact_folder)? {
+            let revocation = match read_claim_file(&path, self.max_claim_size) {
+                Ok(stored) => stored.claim,
+                Err(_) => continue,
+            };
+            match &revocation.specifics {
+                PersistentClaimSpecifics::Revocation(data) if data.claim_id == claim_id => {},
+                _ => continue,
+            }
+            if let Err(e) = check_claim_location(&revocation, artifact_hash, &path) {
+                warn!("ignoring revocation {:?} of claim {}: {}", &path, claim_id, e.description);
+                continue;
+            }
+
+            let signed_path = match SignedFilePath::for_data_file(&path) {
+                Some(p) => p,
+                None => continue,
+            };
+            match self.verifier.verify(&signed_path) {
+                Ok(verification) => result.push((revocation, verification.key)),
+                Err(e) => warn!("ignoring revocation {:?} of claim {}: {}", &path, claim_id, e.description),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn sign_persistent_claim(&self, claim: &PersistentClaim, kind_of_file: &str) -> Result<(), TrustChainError> {
+        let json = io_guarded!(serde_json::to_string(claim), Claims, "error serializing {} {}", kind_of_file, &claim.id);
+        if json.len() as u64 > self.max_claim_size {
+            return err!(Claims, "{} {} has {} bytes, the maximum size is {} bytes", kind_of_file, &claim.id, json.len(), self.max_claim_size);
+        }
+
+        self.sign_and_move_to_registry(&claim.artifact_hash()?, &json, &claim.id, kind_of_file)
+    }
+
+    /// Looks up a claim file among the folders of an artifact's ids, returning the id it was
+    ///  found for
+    fn find_claim_file<'a>(&self, artifact_hashes: &'a [ArtifactId], claim_file_name: &str) -> Result<Option<(&'a ArtifactId, PathBuf)>, TrustChainError> {
+        for artifact_hash in artifact_hashes {
+            let artifact_folder = self.artifact_folder(artifact_hash, false)?;
+            if SignedFilePath::new(&artifact_folder, claim_file_name).data_path.is_file() {
+                return Ok(Some((artifact_hash, artifact_folder)));
+            }
+        }
+        Ok(None)
+    }
 }
 
 impl ClaimRegistry for FileSystemClaimRegistry {
 
-    fn sign_claim(&self, artifact_id: &str, artifact_hash: &Digest, claim_key: &str, claim_value: Option<&str>) -> Result<String, TrustChainError> {
-        let claim_id = uuid::Uuid::new_v4().to_hyphenated().to_string();
-
-        let json = serde_json::json!({
-            "id": &claim_id,
-            "artifact_id": artifact_id,
-            "artifact_hash": to_hex_string(artifact_hash.as_ref()),
-            "claim_key": claim_key,
-            "claim_value": claim_value
-        }).to_string();
+    fn sign_claim(&self, artifact_id: &str, artifact_hash: &ArtifactId, claim_key: &str, claim_value: Option<&str>, comment: Option<&str>) -> Result<String, TrustChainError> {
+        ClaimKind::new(claim_key).validate(claim_value)?;
+        let own_key = self.signer.signing_key()?;
 
-        //TODO check size < 64k
+        let claim = PersistentClaim::new(own_uid(&own_key)?, artifact_id, artifact_hash, comment, PersistentClaimSpecifics::Positive(PersistentPositiveClaimData {
+            claim_kind: claim_key.to_string(),
+            claim_value: claim_value.map(str::to_string),
+        }));
 
-        self.sign_and_move_to_registry(artifact_hash, &json, &claim_id, "claim")?;
+        self.sign_persistent_claim(&claim, "claim")?;
 
-        Ok(claim_id)
+        Ok(claim.id)
     }
 
-    fn revoke_claim(&self, artifact_id: &str, artifact_hash: &Digest, claim_file_name: &str) -> Result<String, TrustChainError> {
-        println!("{:?}", artifact_id);
-        println!("{:?}", artifact_hash);
-        println!("{:?}", claim_file_name);
+    fn revoke_claim(&self, artifact_id: &str, artifact_hashes: &[ArtifactId], claim_id: &str) -> Result<String, TrustChainError> {
+        if Uuid::parse_str(claim_id).is_err() {
+            return err!(ClaimNotFound, "'{}' is not a valid claim id", claim_id);
+        }
 
-        // TODO verify that the claim exists and was signed by us
-        // TODO check that no revocation exists as yet
+        // the revocation is stored with the claim, i.e. for the same hash algorithm
+        let (artifact_hash, artifact_folder) = match self.find_claim_file(artifact_hashes, claim_id)? {
+            Some(found) => found,
+            None => return err!(ClaimNotFound, "claim {} not found for artifact {}", claim_id, artifact_id),
+        };
+        let claim_path = SignedFilePath::new(&artifact_folder, claim_id);
 
-        // write revocation file to temp location
+        let claim_key = self.verifier.verify(&claim_path)?.key;
+        check_claim_location(&read_claim_file(&claim_path.data_path, self.max_claim_size)?.claim, artifact_hash, &claim_path.data_path)?;
+        let own_key = self.signer.signing_key()?;
+        if claim_key.fingerprint != own_key.fingerprint {
+            return err!(ForeignClaim, "claim {} was signed by {} - only claims signed by {} can be revoked", claim_id, claim_key.fingerprint, own_key.fingerprint);
+        }
 
-        // sign revocation file
+        if self.revocations_of(&artifact_folder, artifact_hash, claim_id)?.iter().any(|(_, key)| key.fingerprint == own_key.fingerprint) {
+            return err!(AlreadyRevoked, "claim {} was already revoked", claim_id);
+        }
+
+        let revocation = PersistentClaim::new(own_uid(&own_key)?, artifact_id, artifact_hash, None, PersistentClaimSpecifics::Revocation(PersistentRevocationData {
+            claim_id: claim_id.to_string(),
+        }));
 
+        self.sign_persistent_claim(&revocation, "revocation")?;
 
-        unimplemented!("todo");
+        Ok(revocation.id)
     }
 
-    fn verify_claim(&self, artifact_hash: &Digest, claim_file_name: &str) -> Result<PublicKey, TrustChainError> {
-        //TODO check for revocation
+    fn verify_claim(&self, artifact_hashes: &[ArtifactId], claim_file_name: &str) -> Result<Verification, TrustChainError> {
+        let (artifact_hash, artifact_folder) = match self.find_claim_file(artifact_hashes, claim_file_name)? {
+            Some(found) => found,
+            None => return err!(ClaimNotFound, "claim file {} not found for artifact with hash {}", claim_file_name,
+                                artifact_hashes.iter().map(ArtifactId::to_string).collect::<Vec<_>>().join(" or ")),
+        };
 
-        let artifact_folder = self.artifact_folder(artifact_hash, false)?;
-        if !(artifact_folder.exists() && artifact_folder.is_dir()) {
-            return err!(ClaimNotFound, "claim file {} not found for artifact with hash {}", claim_file_name, to_hex_string(artifact_hash.as_ref()));
+        let path = SignedFilePath::new(&artifact_folder, claim_file_name);
+        let verification = self.verifier.verify(&path)?;
+        let key = &verification.key;
+
+        let claim = read_claim_file(&path.data_path, self.max_claim_size)?.claim;
+        check_claim_location(&claim, artifact_hash, &path.data_path)?;
+
+        // only the claim's signer can revoke it - revocations signed by other keys are ignored
+        for (revocation, revocation_key) in self.revocations_of(&artifact_folder, artifact_hash, &claim.id)? {
+            if revocation_key.fingerprint == key.fingerprint {
+                return err!(Revoked, "claim {} was revoked by {} at {} (revocation {})", &claim.id, revocation_key.fingerprint, humantime::format_rfc3339_seconds(revocation.timestamp), revocation.id);
+            }
+            warn!("ignoring revocation {} of claim {}: it was signed by {} rather than {}", revocation.id, &claim.id, revocation_key.fingerprint, key.fingerprint);
         }
 
-        let path = SignedFilePath::new(&artifact_folder, claim_file_name);
-        Gpg::verify(&path)
+        Ok(verification)
     }
 
 
     fn authenticated_claims_for(&self, artifact: &ArtifactId) -> Result<Box<dyn Iterator<Item=Arc<AuthenticatedClaim>>>, TrustChainError> {
-        let artifact_folder = self.artifact_folder(&artifact.hash, false)?;
+        let artifact_folder = self.artifact_folder(artifact, false)?;
 
         if artifact_folder.is_dir() {
             debug!("looking for claims in {:?}", artifact_folder);
@@ -205,14 +462,17 @@ impl ClaimRegistry for FileSystemClaimRegistry {
                 }
             };
 
-            let iter = dir.filter_map(|e| match e {
+            let verifier = self.verifier.clone();
+            let max_claim_size = self.max_claim_size;
+            let artifact = artifact.clone();
+            let iter = dir.filter_map(move |e| match e {
                 Err(e) => {
                     warn!("{:?}", e); //TODO error reporting
                     None
                 },
-                Ok(entry) if entry.path().is_file() => {
-                    let ac = parse_claim(&entry.path());
-                    ac.map(|a| Arc::new(a))
+                Ok(entry) if entry.path().is_file() && !SignedFilePath::is_signature_file(&entry.path()) => {
+                    let ac = parse_claim(verifier.as_ref(), max_claim_size, &artifact, &entry.path());
+                    ac.map(Arc::new)
                 },
                 _ => None
             });
@@ -223,36 +483,518 @@ impl ClaimRegistry for FileSystemClaimRegistry {
             Ok(Box::new(std::iter::empty()))
         }
     }
+
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError> {
+        self.signer.signing_key()
+    }
+
+    fn claims_by_signer(&self, fingerprint: &str, filter: &ClaimFilter) -> Result<Vec<Arc<AuthenticatedClaim>>, TrustChainError> {
+        let artifacts = match &filter.artifacts {
+            Some(artifacts) => artifacts.clone(),
+            None => self.artifact_hashes()?,
+        };
+
+        let fingerprint = normalize_fingerprint(fingerprint);
+        let mut result = Vec::new();
+        for artifact in &artifacts {
+            result.extend(self.authenticated_claims_for(artifact)?
+                .filter(|c| normalize_fingerprint(&c.common_data().signer.fingerprint) == fingerprint)
+                .filter(|c| filter.matches(c)));
+        }
+        Ok(result)
+    }
+
+    fn migrate_claims(&self) -> Result<usize, TrustChainError> {
+        let own_key = self.signer.signing_key()?;
+        let mut num_migrated = 0;
+
+        for artifact in self.artifact_hashes()? {
+            for path in data_files(&self.artifact_folder(&artifact, false)?)? {
+                let stored = match read_claim_file(&path, self.max_claim_size) {
+                    Ok(s) if s.legacy => s,
+                    Ok(_) => continue,
+                    Err(e) => {
+                        warn!("skipping {:?}: {}", &path, e.description);
+                        continue;
+                    }
+                };
+
+                let signed_path = match SignedFilePath::for_data_file(&path) {
+                    Some(p) => p,
+                    None => continue,
+                };
+                let key = match self.verifier.verify(&signed_path) {
+                    Ok(v) => v.key,
+                    Err(e) => {
+                        warn!("not migrating {:?} because its signature could not be verified: {}", &path, e.description);
+                        continue;
+                    }
+                };
+                if key.fingerprint != own_key.fingerprint {
+                    info!("not migrating {:?} because it was signed by {} - only the signer can migrate it", &path, &key.fingerprint);
+                    continue;
+                }
+
+                let mut claim = stored.claim;
+                claim.uid = own_uid(&own_key)?;
+
+                debug!("migrating claim {:?}", &path);
+                self.sign_persistent_claim(&claim, "claim")?;
+                num_migrated += 1;
+            }
+        }
+
+        Ok(num_migrated)
+    }
+}
+
+fn own_uid(own_key: &PublicKey) -> Result<String, TrustChainError> {
+    match &own_key.uid {
+        Some(uid) => Ok(uid.clone()),
+        None => err!(Gpg, "the signing key {} has no uid", &own_key.fingerprint),
+    }
+}
+
+/// All files in an artifact folder except for signature files
+/// The inverse of `FileSystemClaimRegistry::artifact_folder`
+fn artifact_from_folder_name(name: &str) -> Option<ArtifactId> {
+    let (tree, tagged) = match name.strip_prefix("tree-") {
+        Some(tagged) => (true, tagged),
+        None => (false, name),
+    };
+    let artifact = match tagged.split_once('-') {
+        Some((algorithm, hex)) if tree || algorithm != HashAlgorithm::Sha256.name() => algorithm.parse().and_then(|algorithm| ArtifactId::from_hex(algorithm, hex)).ok()?,
+        Some(_) => return None,
+        None if !tree => ArtifactId::from_hex(HashAlgorithm::Sha256, name).ok()?,
+        None => return None,
+    };
+    Some(ArtifactId { tree, ..artifact })
+}
+
+fn data_files(artifact_folder: &Path) -> Result<Vec<PathBuf>, TrustChainError> {
+    let mut result = Vec::new();
+    for entry in io_guarded!(fs::read_dir(artifact_folder), Claims, "error reading artifact folder {:?}", artifact_folder) {
+        let entry = io_guarded!(entry, Claims, "error reading artifact folder {:?}", artifact_folder);
+        let path = entry.path();
+        if path.is_file() && !SignedFilePath::is_signature_file(&path) {
+            result.push(path);
+        }
+    }
+    Ok(result)
 }
 
-fn parse_claim(path: &Path) -> Option<AuthenticatedClaim> {
-    let metadata = fs::metadata(path).unwrap(); //TODO error handling
-    if metadata.len() > 65536 { //TODO make this configurable
-        warn!("claim file too long: {:?}", path);
+fn read_registry_file<T: DeserializeOwned>(path: &Path, max_size: u64) -> Result<T, TrustChainError> {
+    let metadata = io_guarded!(fs::metadata(path), Io, "error reading metadata of {:?}", path);
+    if metadata.len() > max_size {
+        return err!(Claims, "registry file too long: {:?}", path);
+    }
+
+    let f = io_guarded!(File::open(path), Io, "error opening {:?}", path);
+    Ok(io_guarded!(serde_json::from_reader(&f), Claims, "error parsing {:?}", path))
+}
+
+/// Reads a claim or revocation file, converting it from a legacy format if necessary
+fn read_claim_file(path: &Path, max_size: u64) -> Result<StoredClaim, TrustChainError> {
+    let json: serde_json::Value = read_registry_file(path, max_size)?;
+
+    match json.get("format_version").and_then(|v| v.as_u64()) {
+        Some(CLAIM_FORMAT_VERSION) => {
+            let claim = io_guarded!(serde_json::from_value(json), Claims, "error parsing claim {:?}", path);
+            Ok(StoredClaim { claim, legacy: false })
+        },
+        Some(v) => err!(Claims, "unsupported claim format version {} in {:?}", v, path),
+        None => {
+            let legacy: LegacyClaim = io_guarded!(serde_json::from_value(json), Claims, "error parsing legacy claim {:?}", path);
+            let modified = io_guarded!(fs::metadata(path).and_then(|m| m.modified()), Io, "error reading metadata of {:?}", path);
+            Ok(StoredClaim { claim: legacy.into_persistent_claim(String::new(), modified), legacy: true })
+        },
+    }
+}
+
+/// Checks that a claim or revocation belongs where it is stored: it must be about the artifact
+///  whose folder it is in, and its file name must be its id. Otherwise a validly signed claim
+///  could be copied to another artifact's folder, or a revocation evaded by renaming the claim.
+fn check_claim_location(claim: &PersistentClaim, artifact_hash: &ArtifactId, path: &Path) -> Result<(), TrustChainError> {
+    let claimed_hash = claim.artifact_hash()?;
+    if &claimed_hash != artifact_hash {
+        return err!(Claims, "{:?} is about artifact {}, but it is stored for artifact {}", path, claimed_hash, artifact_hash);
+    }
+    if path.file_name().and_then(|n| n.to_str()) != Some(claim.id.as_str()) {
+        return err!(Claims, "{:?} contains claim {}, which does not match its file name", path, &claim.id);
+    }
+    Ok(())
+}
+
+/// Reads a claim about an artifact from the registry, returning it only if it belongs in the
+///  artifact's folder (see `check_claim_location`) and has a valid signature by a key whose uid
+///  matches the uid stored in the claim. Some signature formats do not authenticate a uid - the
+///  claim's uid is then only covered by the signature.
+fn parse_claim(verifier: &dyn Verifier, max_size: u64, artifact: &ArtifactId, path: &Path) -> Option<AuthenticatedClaim> {
+    let StoredClaim { claim: mut parsed, legacy } = match read_claim_file(path, max_size) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("skipping {:?}: {}", path, e.description);
+            return None;
+        }
+    };
+    if let Err(e) = check_claim_location(&parsed, artifact, path) {
+        warn!("skipping claim {:?}: {}", path, e.description);
         return None;
     }
 
-//    let mut s = String::new();
+    let signed_path = SignedFilePath::for_data_file(path)?;
+    let key = match verifier.verify(&signed_path) {
+        Ok(v) => v.key,
+        Err(e) => {
+            warn!("skipping claim {:?} because its signature could not be verified: {}", path, e.description);
+            return None;
+        }
+    };
 
-    //TODO error handling
-    let f = File::open(path).unwrap();
-    let parsed: PersistentClaim = serde_json::from_reader(&f).unwrap(); //TODO error handling
+    if legacy {
+        parsed.uid = key.uid.clone().unwrap_or_default();
+    }
 
-    //TODO look up certificate, verify signature, confirm uid
+    //TODO gpg reports the key's primary uid - should claims by secondary uids be accepted?
+    if key.uid.as_ref().is_some_and(|uid| uid != &parsed.uid) {
+        warn!("skipping claim {:?}: it states uid {:?}, but it was signed by {} with uid {:?}", path, &parsed.uid, &key.fingerprint, &key.uid);
+        return None;
+    }
 
+    match parsed.into_authenticated_claim(key) {
+        Ok(c) => Some(c),
+        Err(e) => {
+            warn!("skipping claim {:?}: {}", path, e.description);
+            None
+        }
+    }
+}
 
-    Some(parsed.into_authenticated_claim())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::digest::{digest, SHA256};
+    use crate::artifacts::HashAlgorithm;
+    use crate::err::TrustChainErrorKind;
+    use crate::config::MinisignConfig;
+    use crate::ed25519::{Ed25519Signer, Ed25519Verifier, generate_key_file};
+    use crate::gpg::tests::test_gpg;
+    use crate::minisign::MinisignVerifier;
+    use crate::signing::{KeyType, MultiFormatVerifier};
+    use crate::ssh::SshVerifier;
+
+    fn artifact(content: &[u8]) -> ArtifactId {
+        ArtifactId::new(HashAlgorithm::Sha256, digest(&SHA256, content).as_ref().to_vec()).unwrap()
+    }
+
+    /// A registry in a new folder that signs with the test key 'dummy'
+    fn registry() -> FileSystemClaimRegistry {
+        let gpg = Arc::new(test_gpg("dummy"));
+        FileSystemClaimRegistry::new(std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4())), gpg.clone(), gpg, 65536).unwrap()
+    }
+
+    /// Writes a file signed by a given test key to the registry
+    fn sign_as(signing_key: &str, registry: &FileSystemClaimRegistry, artifact_hash: &ArtifactId, id: &str, json: &str) -> SignedFilePath {
+        let path = SignedFilePath::new(&registry.artifact_folder(artifact_hash, true).unwrap(), id);
+        fs::write(&path.data_path, json).unwrap();
+        test_gpg(signing_key).sign("claim", &path).unwrap();
+        path
+    }
 
-//    Some(AuthenticatedClaim::Positive(PositiveClaimData {
-//        common_data: CommonClaimData {
-//            id: Uuid::new_v4(),
-//            uid: "whoever".to_string(),
-//            artifact_id: ArtifactId {
-//                hash: Context::new(&SHA256).finish(),
-//            },
-//            comment: Some("Some comment about something or other".to_string()),
-//            timestamp: SystemTime::now()
-//        },
-//        kind: ClaimKind::new("dummy")
-//    }))
+    fn persistent_claim(uid: &str, artifact_hash: &ArtifactId, specifics: PersistentClaimSpecifics) -> PersistentClaim {
+        PersistentClaim::new(uid.to_string(), "app.jar", artifact_hash, None, specifics)
+    }
+
+    fn positive(claim_kind: &str) -> PersistentClaimSpecifics {
+        PersistentClaimSpecifics::Positive(PersistentPositiveClaimData {
+            claim_kind: claim_kind.to_string(),
+            claim_value: None,
+        })
+    }
+
+    fn foreign_claim(registry: &FileSystemClaimRegistry, artifact_hash: &ArtifactId) -> String {
+        let claim = persistent_claim("Mallory <mallory@example.com>", artifact_hash, positive("reviewed"));
+        sign_as("Mallory", registry, artifact_hash, &claim.id, &serde_json::to_string(&claim).unwrap());
+        claim.id
+    }
+
+    #[test]
+    fn test_revoke_claim() {
+        let registry = registry();
+        let artifact_hash = artifact(b"app");
+        let claim_id = registry.sign_claim("app.jar", &artifact_hash, "reviewed", None, None).unwrap();
+
+        let e = registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &foreign_claim(&registry, &artifact_hash)).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::ForeignClaim), "{:?}", e);
+
+        let revocation_id = registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &claim_id).unwrap();
+        let revocation_path = SignedFilePath::new(&registry.artifact_folder(&artifact_hash, false).unwrap(), &revocation_id);
+        assert!(revocation_path.sig_path.is_file());
+        let revocation: PersistentClaim = read_registry_file(&revocation_path.data_path, 65536).unwrap();
+        assert_eq!(revocation.uid, "dummy <dummy@example.com>");
+        assert!(matches!(revocation.specifics, PersistentClaimSpecifics::Revocation(PersistentRevocationData { claim_id: ref id }) if id == &claim_id));
+
+        let e = registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &claim_id).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::AlreadyRevoked), "{:?}", e);
+
+        for claim_id in [Uuid::new_v4().to_string(), "../other".to_string()] {
+            let e = registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &claim_id).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::ClaimNotFound), "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn test_claims_by_signer() {
+        let registry = registry();
+        let fingerprint = registry.signing_key().unwrap().fingerprint;
+        let app_hash = artifact(b"app");
+        let lib_hash = artifact(b"lib");
+        let reviewed_id = registry.sign_claim("app.jar", &app_hash, "reviewed", None, None).unwrap();
+        let tested_id = registry.sign_claim("app.jar", &app_hash, "tested", None, None).unwrap();
+        let lib_id = registry.sign_claim("lib.jar", &lib_hash, "reviewed", None, None).unwrap();
+        let revocation_id = registry.revoke_claim("app.jar", std::slice::from_ref(&app_hash), &reviewed_id).unwrap();
+        let foreign_id = foreign_claim(&registry, &app_hash);
+
+        // files that are not validly signed claims, and folders that are not artifact folders
+        fs::write(registry.artifact_folder(&app_hash, false).unwrap().join(Uuid::new_v4().to_string()), "{}").unwrap();
+        fs::write(registry.artifact_folder(&lib_hash, false).unwrap().join(Uuid::new_v4().to_string()), "no claim").unwrap();
+        fs::create_dir_all(registry.root.join("not-a-hash")).unwrap();
+
+        let claim_ids = |fingerprint: &str, filter: &ClaimFilter| {
+            let mut result = registry.claims_by_signer(fingerprint, filter).unwrap().iter()
+                .map(|c| c.common_data().id.to_string())
+                .collect::<Vec<_>>();
+            result.sort();
+            result
+        };
+        let sorted = |mut ids: Vec<&String>| {
+            ids.sort();
+            ids.into_iter().cloned().collect::<Vec<_>>()
+        };
+
+        assert_eq!(claim_ids(&fingerprint, &ClaimFilter::default()), sorted(vec!(&reviewed_id, &tested_id, &lib_id, &revocation_id)));
+        assert_eq!(claim_ids(&fingerprint.to_lowercase(), &ClaimFilter::default()).len(), 4);
+
+        let mallory = registry.authenticated_claims_for(&app_hash).unwrap()
+            .find(|c| c.common_data().id.to_string() == foreign_id).unwrap()
+            .common_data().signer.fingerprint.clone();
+        assert_eq!(claim_ids(&mallory, &ClaimFilter::default()), vec!(foreign_id));
+
+        let filter = ClaimFilter { artifacts: Some(vec!(lib_hash.clone())), claim_key: None };
+        assert_eq!(claim_ids(&fingerprint, &filter), vec!(lib_id.clone()));
+
+        // revocations have no claim key, so they are kept by the claim key filter
+        let filter = ClaimFilter { artifacts: Some(vec!(app_hash.clone())), claim_key: Some("reviewed".to_string()) };
+        assert_eq!(claim_ids(&fingerprint, &filter), sorted(vec!(&reviewed_id, &revocation_id)));
+
+        assert!(claim_ids(&fingerprint, &ClaimFilter { artifacts: Some(vec!(artifact(b"other"))), claim_key: None }).is_empty());
+
+        // an artifact is matched by any of its ids, whichever algorithm the claim's folder uses
+        let blake3 = ArtifactId::new(HashAlgorithm::Blake3, vec!(0xAB; 32)).unwrap();
+        let blake3_id = registry.sign_claim("lib.jar", &blake3, "tested", None, None).unwrap();
+        let filter = ClaimFilter { artifacts: Some(vec!(lib_hash.clone(), blake3)), claim_key: None };
+        assert_eq!(claim_ids(&fingerprint, &filter), sorted(vec!(&lib_id, &blake3_id)));
+    }
+
+    #[test]
+    fn test_artifact_folders() {
+        let registry = registry();
+        let sha256 = ArtifactId::new(HashAlgorithm::Sha256, vec!(0xAB; 32)).unwrap();
+        let blake3 = ArtifactId::new(HashAlgorithm::Blake3, vec!(0xAB; 32)).unwrap();
+        let sha512 = ArtifactId::new(HashAlgorithm::Sha512, vec!(0x01; 64)).unwrap();
+        let tree = ArtifactId { tree: true, ..sha256.clone() };
+
+        assert_eq!(registry.artifact_folder(&sha256, true).unwrap(), registry.root.join("ab".repeat(32)));
+        assert_eq!(registry.artifact_folder(&tree, true).unwrap(), registry.root.join(format!("tree-sha256-{}", "ab".repeat(32))));
+        assert_eq!(registry.artifact_folder(&blake3, true).unwrap(), registry.root.join(format!("blake3-{}", "ab".repeat(32))));
+        assert_eq!(registry.artifact_folder(&sha512, true).unwrap(), registry.root.join(format!("sha512-{}", "01".repeat(64))));
+
+        // unexpected folders are skipped, including SHA-256 hashes with an explicit prefix
+        for name in ["sha256-".to_string() + &"ab".repeat(32), "md5-".to_string() + &"ab".repeat(16), "blake3-ab".to_string(), "other".to_string(),
+                     "tree-".to_string() + &"ab".repeat(32)] {
+            fs::create_dir_all(registry.root.join(name)).unwrap();
+        }
+        fs::write(registry.root.join("cd".repeat(32)), "").unwrap();
+
+        let mut hashes: Vec<String> = registry.artifact_hashes().unwrap().iter().map(ArtifactId::to_string).collect();
+        hashes.sort();
+        assert_eq!(hashes, vec!(blake3.to_string(), sha256.to_string(), sha512.to_string(), tree.to_string()));
+    }
+
+    #[test]
+    fn test_verify_claim() {
+        let registry = registry();
+        let artifact_hash = artifact(b"app");
+        let claim_id = registry.sign_claim("app.jar", &artifact_hash, "reviewed", None, None).unwrap();
+
+        let key = registry.verify_claim(std::slice::from_ref(&artifact_hash), &claim_id).unwrap();
+        assert_eq!(key.key.fingerprint, registry.signing_key().unwrap().fingerprint);
+        let e = registry.verify_claim(&[artifact(b"other")], &claim_id).err().unwrap();
+        assert!(matches!(e.kind, TrustChainErrorKind::ClaimNotFound), "{:?}", e);
+
+        // a validly signed claim in another artifact's folder
+        let claim_path = SignedFilePath::new(&registry.artifact_folder(&artifact_hash, false).unwrap(), &claim_id);
+        let other_hash = artifact(b"other");
+        claim_path.copy_to(&SignedFilePath::new(&registry.artifact_folder(&other_hash, true).unwrap(), &claim_id), "claim").unwrap();
+        let e = registry.verify_claim(std::slice::from_ref(&other_hash), &claim_id).err().unwrap();
+        assert!(matches!(e.kind, TrustChainErrorKind::Claims), "{:?}", e);
+
+        // a revocation by anybody but the claim's signer is ignored
+        let revocation = persistent_claim("Mallory <mallory@example.com>", &artifact_hash, PersistentClaimSpecifics::Revocation(PersistentRevocationData {
+            claim_id: claim_id.clone(),
+        }));
+        sign_as("Mallory", &registry, &artifact_hash, &revocation.id, &serde_json::to_string(&revocation).unwrap());
+        assert!(registry.verify_claim(std::slice::from_ref(&artifact_hash), &claim_id).is_ok());
+
+        registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &claim_id).unwrap();
+        let e = registry.verify_claim(std::slice::from_ref(&artifact_hash), &claim_id).err().unwrap();
+        assert!(matches!(e.kind, TrustChainErrorKind::Revoked), "{:?}", e);
+    }
+
+    #[test]
+    fn test_authenticated_claims_for() {
+        let registry = registry();
+        let claim_ids = |artifact_hash: &ArtifactId| registry.authenticated_claims_for(artifact_hash).unwrap()
+            .map(|c| match c.as_ref() {
+                AuthenticatedClaim::Positive(data) => data.common_data.id.to_string(),
+                AuthenticatedClaim::Revocation(data) => data.common_data.id.to_string(),
+            })
+            .collect::<Vec<_>>();
+        let sign = |signing_key: &str, uid: &str, artifact_hash: &ArtifactId| {
+            let claim = persistent_claim(uid, artifact_hash, positive("reviewed"));
+            sign_as(signing_key, &registry, artifact_hash, &claim.id, &serde_json::to_string(&claim).unwrap())
+        };
+
+        let artifact_hash = artifact(b"app");
+        let claim_path = sign("dummy", "dummy <dummy@example.com>", &artifact_hash);
+        let claim_id = claim_path.data_path.file_name().unwrap().to_str().unwrap().to_string();
+        let claims = registry.authenticated_claims_for(&artifact_hash).unwrap().collect::<Vec<_>>();
+        assert_eq!(claims.len(), 1);
+        match claims[0].as_ref() {
+            AuthenticatedClaim::Positive(data) => {
+                assert_eq!(data.common_data.id.to_string(), claim_id);
+                assert_eq!(data.common_data.uid, "dummy <dummy@example.com>");
+                assert_eq!(data.common_data.signer.fingerprint, registry.signing_key().unwrap().fingerprint);
+                assert_eq!(data.kind.claim_kind, "reviewed");
+            },
+            AuthenticatedClaim::Revocation(_) => panic!("expected a positive claim"),
+        }
+
+        // a validly signed claim copied to another artifact's folder, or stored under another name
+        let other_hash = artifact(b"other");
+        claim_path.copy_to(&SignedFilePath::new(&registry.artifact_folder(&other_hash, true).unwrap(), &claim_id), "claim").unwrap();
+        assert!(claim_ids(&other_hash).is_empty());
+        claim_path.copy_to(&SignedFilePath::new(&registry.artifact_folder(&artifact_hash, false).unwrap(), &Uuid::new_v4().to_string()), "claim").unwrap();
+        assert_eq!(claim_ids(&artifact_hash), vec!(claim_id.clone()));
+
+        // a claim modified after signing
+        let tampered_path = sign("dummy", "dummy <dummy@example.com>", &artifact_hash).data_path;
+        let json = fs::read_to_string(&tampered_path).unwrap().replace("reviewed", "audited");
+        fs::write(&tampered_path, json).unwrap();
+        assert_eq!(claim_ids(&artifact_hash), vec!(claim_id.clone()));
+
+        // a claim stating another uid than its signer's
+        sign("Mallory", "dummy <dummy@example.com>", &artifact_hash);
+        assert_eq!(claim_ids(&artifact_hash), vec!(claim_id));
+    }
+
+    #[test]
+    fn test_mixed_signature_formats() {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        let key_file = folder.join("ci.key");
+        let fingerprint = generate_key_file(&key_file, "CI bot <ci@example.org>").unwrap();
+        let verifier = MultiFormatVerifier {
+            openpgp: Arc::new(test_gpg("dummy")),
+            ed25519: Arc::new(Ed25519Verifier),
+            ssh: Arc::new(SshVerifier),
+            minisign: Arc::new(MinisignVerifier::new(&MinisignConfig::default()).unwrap()),
+        };
+        let registry = FileSystemClaimRegistry::new(folder.join("registry"), Arc::new(Ed25519Signer::new(Some(&key_file)).unwrap()),
+            Arc::new(verifier), 65536).unwrap();
+
+        let artifact_hash = artifact(b"app");
+        let claim_id = registry.sign_claim("app.jar", &artifact_hash, "reviewed", None, None).unwrap();
+        let foreign_id = foreign_claim(&registry, &artifact_hash);
+
+        let mut signers = registry.authenticated_claims_for(&artifact_hash).unwrap()
+            .map(|c| match c.as_ref() {
+                AuthenticatedClaim::Positive(data) => (data.common_data.id.to_string(), data.common_data.signer.key_type, data.common_data.uid.clone()),
+                AuthenticatedClaim::Revocation(_) => panic!("expected a positive claim"),
+            })
+            .collect::<Vec<_>>();
+        signers.sort_by_key(|(_, key_type, _)| *key_type != KeyType::Ed25519);
+        assert_eq!(signers, vec!(
+            (claim_id.clone(), KeyType::Ed25519, "CI bot <ci@example.org>".to_string()),
+            (foreign_id, KeyType::OpenPgp, "Mallory <mallory@example.com>".to_string())));
+        assert_eq!(registry.verify_claim(std::slice::from_ref(&artifact_hash), &claim_id).unwrap().key.fingerprint, fingerprint);
+    }
+
+    #[test]
+    fn test_claim_format() {
+        let registry = registry();
+        let artifact_hash = artifact(b"app");
+        let claim_id = registry.sign_claim("app.jar", &artifact_hash, "x-checked", Some("yes"), Some("looks good")).unwrap();
+        let path = SignedFilePath::new(&registry.artifact_folder(&artifact_hash, false).unwrap(), &claim_id).data_path;
+
+        let stored = read_claim_file(&path, 65536).unwrap();
+        assert!(!stored.legacy);
+        assert_eq!(stored.claim.format_version, CLAIM_FORMAT_VERSION);
+        assert_eq!(stored.claim.id, claim_id);
+        assert_eq!(stored.claim.uid, "dummy <dummy@example.com>");
+        assert_eq!(stored.claim.artifact_hash, to_hex_string(&artifact_hash.hash));
+        assert_eq!(stored.claim.comment.as_deref(), Some("looks good"));
+        match stored.claim.specifics {
+            PersistentClaimSpecifics::Positive(data) => {
+                assert_eq!(data.claim_kind, "x-checked");
+                assert_eq!(data.claim_value.as_deref(), Some("yes"));
+            },
+            PersistentClaimSpecifics::Revocation(_) => panic!("expected a positive claim"),
+        }
+
+        assert!(matches!(read_claim_file(&path, 16), Err(TrustChainError { kind: TrustChainErrorKind::Claims, .. })));
+
+        let json = fs::read_to_string(&path).unwrap().replace("\"format_version\":1", "\"format_version\":2");
+        fs::write(&path, json).unwrap();
+        assert!(matches!(read_claim_file(&path, 65536), Err(TrustChainError { kind: TrustChainErrorKind::Claims, .. })));
+    }
+
+    #[test]
+    fn test_legacy_claims() {
+        let registry = registry();
+        let artifact_hash = artifact(b"app");
+        let legacy_claim = |signing_key: &str| {
+            let claim_id = Uuid::new_v4().to_string();
+            let json = format!(r#"{{"id":"{}","artifact_id":"app.jar","artifact_hash":"{}","claim_key":"reviewed","claim_value":null}}"#,
+                               claim_id, to_hex_string(&artifact_hash.hash));
+            let path = sign_as(signing_key, &registry, &artifact_hash, &claim_id, &json);
+            assert!(read_claim_file(&path.data_path, 65536).unwrap().legacy);
+            claim_id
+        };
+        let own_claim_id = legacy_claim("dummy");
+        let foreign_claim_id = legacy_claim("Mallory");
+
+        // the uid is taken from the signature
+        let mut claims = registry.authenticated_claims_for(&artifact_hash).unwrap()
+            .map(|c| match c.as_ref() {
+                AuthenticatedClaim::Positive(data) => (data.common_data.id.to_string(), data.common_data.uid.clone(), data.kind.claim_kind.clone()),
+                AuthenticatedClaim::Revocation(_) => panic!("expected a positive claim"),
+            })
+            .collect::<Vec<_>>();
+        claims.sort();
+        let mut expected = vec!(
+            (own_claim_id.clone(), "dummy <dummy@example.com>".to_string(), "reviewed".to_string()),
+            (foreign_claim_id.clone(), "Mallory <mallory@example.com>".to_string(), "reviewed".to_string()),
+        );
+        expected.sort();
+        assert_eq!(claims, expected);
+
+        // only the signer can migrate a legacy claim
+        assert_eq!(registry.migrate_claims().unwrap(), 1);
+        assert_eq!(registry.migrate_claims().unwrap(), 0);
+        let folder = registry.artifact_folder(&artifact_hash, false).unwrap();
+        assert!(!read_claim_file(&folder.join(&own_claim_id), 65536).unwrap().legacy);
+        assert!(read_claim_file(&folder.join(&foreign_claim_id), 65536).unwrap().legacy);
+        assert!(registry.verify_claim(std::slice::from_ref(&artifact_hash), &own_claim_id).is_ok());
+        registry.revoke_claim("app.jar", std::slice::from_ref(&artifact_hash), &own_claim_id).unwrap();
+    }
 }
@@ -0,0 +1,263 @@
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::err::*;
+
+
+/// The kinds of claims with a well-known meaning. Other kinds of claims must start with 'x-'
+///  to avoid clashes with kinds that are added later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WellKnownClaimKind {
+    /// the artifact's source was reviewed, optionally with a review depth as value
+    Reviewed,
+    /// the artifact was rebuilt from its source with an identical result
+    BuiltReproducibly,
+    /// the artifact's license as an SPDX expression, e.g. 'MIT OR Apache-2.0'
+    License,
+    /// the artifact has known vulnerabilities, listed as value, e.g. 'CVE-2021-44228'
+    Vulnerable,
+    /// the artifact contains malicious code
+    Malicious,
+    /// the artifact was tested
+    Tested,
+}
+
+impl WellKnownClaimKind {
+    pub const ALL: [WellKnownClaimKind; 6] = [
+        WellKnownClaimKind::Reviewed,
+        WellKnownClaimKind::BuiltReproducibly,
+        WellKnownClaimKind::License,
+        WellKnownClaimKind::Vulnerable,
+        WellKnownClaimKind::Malicious,
+        WellKnownClaimKind::Tested,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WellKnownClaimKind::Reviewed => "reviewed",
+            WellKnownClaimKind::BuiltReproducibly => "built-reproducibly",
+            WellKnownClaimKind::License => "license",
+            WellKnownClaimKind::Vulnerable => "vulnerable",
+            WellKnownClaimKind::Malicious => "malicious",
+            WellKnownClaimKind::Tested => "tested",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<WellKnownClaimKind> {
+        WellKnownClaimKind::ALL.iter().copied().find(|k| k.name() == name)
+    }
+
+    /// Negative claims speak against using an artifact
+    pub fn is_negative(self) -> bool {
+        matches!(self, WellKnownClaimKind::Vulnerable | WellKnownClaimKind::Malicious)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReviewDepth {
+    /// a quick look for anything suspicious
+    Skimmed,
+    /// parts of the code were reviewed thoroughly
+    Partial,
+    /// all of the code was reviewed thoroughly
+    Full,
+}
+
+impl FromStr for ReviewDepth {
+    type Err = TrustChainError;
+
+    fn from_str(s: &str) -> Result<ReviewDepth, TrustChainError> {
+        match s {
+            "skimmed" => Ok(ReviewDepth::Skimmed),
+            "partial" => Ok(ReviewDepth::Partial),
+            "full" => Ok(ReviewDepth::Full),
+            _ => err!(InvalidClaim, "invalid review depth {:?} - it must be 'skimmed', 'partial' or 'full'", s),
+        }
+    }
+}
+
+impl std::fmt::Display for ReviewDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ReviewDepth::Skimmed => "skimmed",
+            ReviewDepth::Partial => "partial",
+            ReviewDepth::Full => "full",
+        })
+    }
+}
+
+/// A claim's value, interpreted according to the claim's kind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimValue {
+    None,
+    ReviewDepth(ReviewDepth),
+    /// an SPDX license expression
+    License(String),
+    /// advisory ids like 'CVE-2021-44228' or 'GHSA-jfh8-c2jp-5v3q'
+    Advisories(Vec<String>),
+    /// the value of a custom claim, which has no schema
+    Custom(String),
+}
+
+pub struct ClaimKind {
+    pub claim_kind: String,
+}
+
+impl ClaimKind {
+    pub fn new(kind: &str) -> ClaimKind {
+        ClaimKind {
+            claim_kind: kind.to_string()
+        }
+    }
+
+    pub fn well_known(&self) -> Option<WellKnownClaimKind> {
+        WellKnownClaimKind::from_name(&self.claim_kind)
+    }
+
+    /// Checks the kind's name and parses a value according to the kind's schema. This is done
+    ///  before signing a claim - claims that are already in the registry may have been signed
+    ///  before the kinds were typed and are interpreted leniently, see `typed_value`.
+    pub fn validate(&self, value: Option<&str>) -> Result<ClaimValue, TrustChainError> {
+        if self.well_known().is_none() && !is_valid_custom_kind(&self.claim_kind) {
+            let names: Vec<&str> = WellKnownClaimKind::ALL.iter().map(|k| k.name()).collect();
+            return err!(InvalidClaim, "unknown claim kind '{}' - it must be one of {} or a custom kind 'x-...'", self.claim_kind, names.join(", "));
+        }
+        self.parse_value(value)
+    }
+
+    /// The value of a claim from the registry, or `ClaimValue::None` if it does not match the schema
+    pub fn typed_value(&self, value: Option<&str>) -> ClaimValue {
+        self.parse_value(value).unwrap_or(ClaimValue::None)
+    }
+
+    fn parse_value(&self, value: Option<&str>) -> Result<ClaimValue, TrustChainError> {
+        let kind = match self.well_known() {
+            Some(kind) => kind,
+            None => return Ok(value.map(|v| ClaimValue::Custom(v.to_string())).unwrap_or(ClaimValue::None)),
+        };
+
+        match (kind, value) {
+            (WellKnownClaimKind::Reviewed, None) => Ok(ClaimValue::None),
+            (WellKnownClaimKind::Reviewed, Some(depth)) => Ok(ClaimValue::ReviewDepth(depth.parse()?)),
+            (WellKnownClaimKind::License, Some(expression)) => {
+                validate_spdx_expression(expression)?;
+                Ok(ClaimValue::License(expression.to_string()))
+            },
+            (WellKnownClaimKind::Vulnerable, Some(advisories)) => Ok(ClaimValue::Advisories(parse_advisories(advisories)?)),
+            (WellKnownClaimKind::License, None) | (WellKnownClaimKind::Vulnerable, None) =>
+                err!(InvalidClaim, "claims of kind '{}' require a value", kind.name()),
+            (WellKnownClaimKind::BuiltReproducibly, None) | (WellKnownClaimKind::Malicious, None) | (WellKnownClaimKind::Tested, None) => Ok(ClaimValue::None),
+            (_, Some(_)) => err!(InvalidClaim, "claims of kind '{}' have no value - please use a comment instead", kind.name()),
+        }
+    }
+}
+
+fn is_valid_custom_kind(name: &str) -> bool {
+    Regex::new(r"^x-[a-z0-9]+(-[a-z0-9]+)*$").unwrap().is_match(name)
+}
+
+/// Parses a comma separated list of advisory ids
+fn parse_advisories(s: &str) -> Result<Vec<String>, TrustChainError> {
+    let regex = Regex::new(r"^(CVE-\d{4}-\d{4,}|GHSA(-[23456789cfghjmpqrvwx]{4}){3}|RUSTSEC-\d{4}-\d{4})$").unwrap();
+
+    let mut result = Vec::new();
+    for advisory in s.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+        if !regex.is_match(advisory) {
+            return err!(InvalidClaim, "invalid advisory id {:?} - expected e.g. 'CVE-2021-44228'", advisory);
+        }
+        result.push(advisory.to_string());
+    }
+
+    if result.is_empty() {
+        return err!(InvalidClaim, "a vulnerability claim requires at least one advisory id");
+    }
+    Ok(result)
+}
+
+/// Checks the syntax of an SPDX license expression, e.g. '(MIT OR Apache-2.0) AND BSD-3-Clause'
+///  or 'GPL-2.0-or-later WITH Classpath-exception-2.0'. License ids are not checked against the
+///  SPDX license list.
+fn validate_spdx_expression(expression: &str) -> Result<(), TrustChainError> {
+    let id = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9.\-]*\+?$").unwrap();
+    let tokens = expression.replace('(', " ( ").replace(')', " ) ");
+
+    let mut depth = 0;
+    // true if the next token must be a license id or '(', false if it must be an operator or ')'
+    let mut expect_operand = true;
+    let mut after_with = false;
+    for token in tokens.split_whitespace() {
+        match token {
+            "(" if expect_operand && !after_with => depth += 1,
+            ")" if !expect_operand && depth > 0 => depth -= 1,
+            "AND" | "OR" if !expect_operand => expect_operand = true,
+            "WITH" if !expect_operand => {
+                expect_operand = true;
+                after_with = true;
+                continue;
+            },
+            t if expect_operand && id.is_match(t) => expect_operand = false,
+            _ => return err!(InvalidClaim, "invalid SPDX license expression {:?} at {:?}", expression, token),
+        }
+        after_with = false;
+    }
+
+    if expect_operand || depth != 0 {
+        return err!(InvalidClaim, "incomplete SPDX license expression {:?}", expression);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(kind: &str, value: Option<&str>) -> Result<ClaimValue, TrustChainError> {
+        ClaimKind::new(kind).validate(value)
+    }
+
+    #[test]
+    fn test_claim_kinds() {
+        for kind in WellKnownClaimKind::ALL {
+            assert_eq!(WellKnownClaimKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(validate("x-fuzzed", None).unwrap(), ClaimValue::None);
+        assert_eq!(validate("x-fuzzed-2", Some("1 hour")).unwrap(), ClaimValue::Custom("1 hour".to_string()));
+        for kind in ["fuzzed", "x-", "x-Fuzzed", "x-fuzzed-", "x--fuzzed", "Reviewed", ""] {
+            assert!(matches!(validate(kind, None).unwrap_err().kind, TrustChainErrorKind::InvalidClaim), "{}", kind);
+        }
+    }
+
+    #[test]
+    fn test_claim_values() {
+        assert_eq!(validate("reviewed", None).unwrap(), ClaimValue::None);
+        assert_eq!(validate("reviewed", Some("partial")).unwrap(), ClaimValue::ReviewDepth(ReviewDepth::Partial));
+        assert!(ReviewDepth::Skimmed < ReviewDepth::Partial && ReviewDepth::Partial < ReviewDepth::Full);
+        assert_eq!(validate("vulnerable", Some("CVE-2021-44228, GHSA-jfh8-c2jp-5v3q,RUSTSEC-2021-0001")).unwrap(),
+                   ClaimValue::Advisories(vec!("CVE-2021-44228".to_string(), "GHSA-jfh8-c2jp-5v3q".to_string(), "RUSTSEC-2021-0001".to_string())));
+        assert_eq!(validate("license", Some("MIT OR Apache-2.0")).unwrap(), ClaimValue::License("MIT OR Apache-2.0".to_string()));
+        assert_eq!(validate("malicious", None).unwrap(), ClaimValue::None);
+
+        for (kind, value) in [("reviewed", Some("thorough")), ("license", None), ("vulnerable", None), ("vulnerable", Some(" , ")),
+                              ("vulnerable", Some("CVE-2021-44228,CVE-21-1")), ("vulnerable", Some("GHSA-jfh8-c2jp-5v3a")),
+                              ("tested", Some("yes")), ("built-reproducibly", Some("twice"))] {
+            assert!(matches!(validate(kind, value).unwrap_err().kind, TrustChainErrorKind::InvalidClaim), "{} {:?}", kind, value);
+        }
+
+        // values from the registry are interpreted leniently
+        assert_eq!(ClaimKind::new("reviewed").typed_value(Some("thorough")), ClaimValue::None);
+        assert_eq!(ClaimKind::new("tested").typed_value(Some("yes")), ClaimValue::None);
+    }
+
+    #[test]
+    fn test_spdx_expressions() {
+        for expression in ["MIT", "GPL-2.0+", "MIT OR Apache-2.0", "(MIT OR Apache-2.0) AND BSD-3-Clause",
+                           "GPL-2.0-or-later WITH Classpath-exception-2.0", "((MIT))", "LicenseRef-custom.1 AND (ISC OR Zlib)"] {
+            assert!(validate_spdx_expression(expression).is_ok(), "{}", expression);
+        }
+        for expression in ["", "MIT OR", "OR MIT", "MIT Apache-2.0", "(MIT", "MIT)", "()", "MIT AND OR ISC",
+                           "MIT WITH (Classpath-exception-2.0)", "MIT WITH", "-MIT", "MIT/Apache-2.0", "M+IT"] {
+            assert!(matches!(validate_spdx_expression(expression).unwrap_err().kind, TrustChainErrorKind::InvalidClaim), "{}", expression);
+        }
+    }
+}
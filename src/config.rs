@@ -0,0 +1,558 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::artifacts::HashAlgorithm;
+use crate::err::*;
+
+
+const USER_CONFIG_FILE_NAME: &str = "config.toml";
+const PROJECT_CONFIG_FILE_NAME: &str = ".trust-chain-checker.toml";
+
+const ENV_PREFIX: &str = "TRUST_CHAIN_CHECKER_";
+
+/// The tool's settings. They are assembled from several layers, each overriding the previous
+///  ones:
+///
+/// * built-in defaults
+/// * the user's config file, e.g. '~/.config/trust-chain-checker/config.toml' on Linux
+/// * the project's config file '.trust-chain-checker.toml' in the current folder or one of its
+///   parents - it may only contain the repository paths, see `ProjectConfig`
+/// * environment variables 'TRUST_CHAIN_CHECKER_...'
+/// * command line options
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub registry: RegistryConfig,
+    pub repositories: RepositoriesConfig,
+    pub maven: MavenConfig,
+    pub signing: SigningConfig,
+    pub gpg: GpgConfig,
+    pub ed25519: Ed25519Config,
+    pub ssh: SshConfig,
+    pub minisign: MinisignConfig,
+    pub trust: TrustConfig,
+    pub policy: PolicyConfig,
+
+    /// the config files that were read, in the order of precedence
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+    /// the signing key from the environment - which setting it goes to depends on the signature
+    ///  backend, which the command line may still change, see `apply_signing_key`
+    #[serde(skip)]
+    env_signing_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RegistryConfig {
+    /// the root folder of the claim registry
+    pub path: Option<PathBuf>,
+    /// the maximum size of a claim file in bytes - bigger files are ignored
+    pub max_claim_size: Option<u64>,
+}
+
+/// The settings a project's config file may contain. A project is not necessarily trusted, e.g.
+///  when a CI job checks a freshly checked out branch, so its config file must not choose
+///  executables, keys, the registry or whom to trust.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ProjectConfig {
+    repositories: RepositoriesConfig,
+}
+
+/// The root folders of the artifact repositories, per kind of repository
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RepositoriesConfig {
+    pub maven: Option<PathBuf>,
+    /// npm's cache folder
+    pub npm: Option<PathBuf>,
+    /// a 'node_modules' folder with unpacked npm packages
+    pub node_modules: Option<PathBuf>,
+    /// the Cargo home folder
+    pub cargo: Option<PathBuf>,
+    /// the folder that paths of local files and folders are relative to
+    pub local: Option<PathBuf>,
+    /// an OCI image layout folder or tarball, e.g. written by 'docker save'
+    pub oci: Option<PathBuf>,
+    /// a folder with Python wheels and sdists, e.g. pip's wheel cache or a wheelhouse written by 'pip download -d'
+    pub python: Option<PathBuf>,
+}
+
+/// The implementation that signs and verifies claims
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureBackend {
+    /// the 'gpg' executable with its own keyring
+    Gpg,
+    /// a plain Ed25519 key file, e.g. for CI bots
+    Ed25519,
+    /// an SSH key, signing with 'ssh-keygen'
+    Ssh,
+}
+
+impl std::str::FromStr for SignatureBackend {
+    type Err = TrustChainError;
+
+    fn from_str(s: &str) -> Result<SignatureBackend, TrustChainError> {
+        match s {
+            "gpg" => Ok(SignatureBackend::Gpg),
+            "ed25519" => Ok(SignatureBackend::Ed25519),
+            "ssh" => Ok(SignatureBackend::Ssh),
+            _ => err!(Config, "invalid signature backend {:?} - it must be 'gpg', 'ed25519' or 'ssh'", s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MavenConfig {
+    /// the mvn executable for resolving a project's dependencies, 'mvn' on the PATH by default
+    pub executable: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SigningConfig {
+    /// 'gpg' by default
+    pub backend: Option<SignatureBackend>,
+    /// the algorithm artifacts are hashed with for new claims, 'sha256' by default
+    pub hash_algorithm: Option<HashAlgorithm>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GpgConfig {
+    /// the gpg executable, 'gpg' on the PATH by default
+    pub executable: Option<PathBuf>,
+    /// passed to gpg as '--homedir' if present
+    pub homedir: Option<PathBuf>,
+    /// a keyring file with the certificates of all signers, e.g. exported with 'gpg --export' -
+    ///  if present, signatures are verified against it instead of the home directory's keyring
+    pub keyring: Option<PathBuf>,
+    /// the key id or fingerprint to sign claims with, gpg's default key if none is configured
+    pub signing_key: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Ed25519Config {
+    /// the key file to sign claims with, created with 'generate-key'
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SshConfig {
+    /// the ssh-keygen executable, 'ssh-keygen' on the PATH by default
+    pub executable: Option<PathBuf>,
+    /// the private key to sign claims with, or its public key if the private key is in an SSH agent
+    pub key_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MinisignConfig {
+    /// the base64 encoded public keys whose signatures are verified, i.e. the second line of
+    ///  their 'minisign.pub' files
+    pub public_keys: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TrustConfig {
+    /// the file with the trust levels of signing keys
+    pub store: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PolicyConfig {
+    /// the file declaring which claims artifacts need to be accepted
+    pub path: Option<PathBuf>,
+}
+
+impl Config {
+    /// The user's configuration file, e.g. '~/.config/trust-chain-checker/config.toml' on Linux
+    pub fn user_config_file() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("trust-chain-checker").join(USER_CONFIG_FILE_NAME))
+    }
+
+    /// The project's configuration file, looked up in the current folder and its parents
+    pub fn project_config_file() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .map(|d| d.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|f| f.is_file())
+    }
+
+    /// Loads all layers except for the command line options, which are applied by the caller
+    pub fn load() -> Result<Config, TrustChainError> {
+        Config::load_from(Config::user_config_file(), Config::project_config_file())
+    }
+
+    /// Loads all layers except for the command line options from the given config files, which
+    ///  are skipped if they do not exist
+    pub fn load_from(user_config_file: Option<PathBuf>, project_config_file: Option<PathBuf>) -> Result<Config, TrustChainError> {
+        let mut config = Config::defaults();
+
+        if let Some(file) = user_config_file.filter(|f| f.is_file()) {
+            config.merge(Config::read(&file)?);
+            config.sources.push(file);
+        }
+        if let Some(file) = project_config_file.filter(|f| f.is_file()) {
+            config.merge(Config::read_project(&file)?);
+            config.sources.push(file);
+        }
+
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    fn defaults() -> Config {
+        let home_dir = dirs::home_dir();
+        let cargo_home = std::env::var_os("CARGO_HOME").map(PathBuf::from)
+            .or_else(|| home_dir.as_ref().map(|h| h.join(".cargo")));
+
+        Config {
+            registry: RegistryConfig {
+                path: home_dir.as_ref().map(|h| h.join(".trust-chain-checker").join("registry")),
+                max_claim_size: Some(65536),
+            },
+            repositories: RepositoriesConfig {
+                maven: home_dir.as_ref().map(|h| h.join(".m2").join("repository")),
+                npm: home_dir.as_ref().map(|h| h.join(".npm")),
+                node_modules: Some(PathBuf::from("node_modules")),
+                cargo: cargo_home,
+                local: Some(PathBuf::from(".")),
+                oci: None,
+                python: dirs::cache_dir().map(|c| c.join("pip").join("wheels")),
+            },
+            maven: MavenConfig {
+                executable: Some(PathBuf::from("mvn")),
+            },
+            signing: SigningConfig {
+                backend: Some(SignatureBackend::Gpg),
+                hash_algorithm: Some(HashAlgorithm::Sha256),
+            },
+            gpg: GpgConfig {
+                executable: Some(PathBuf::from("gpg")),
+                homedir: None,
+                keyring: None,
+                signing_key: None,
+            },
+            ed25519: Ed25519Config {
+                key_file: home_dir.as_ref().map(|h| h.join(".trust-chain-checker").join("ed25519.key")),
+            },
+            ssh: SshConfig {
+                executable: Some(PathBuf::from("ssh-keygen")),
+                key_file: home_dir.as_ref().map(|h| h.join(".ssh").join("id_ed25519")),
+            },
+            minisign: MinisignConfig {
+                public_keys: None,
+            },
+            trust: TrustConfig {
+                store: home_dir.as_ref().map(|h| h.join(".trust-chain-checker").join("trust.toml")),
+            },
+            policy: PolicyConfig {
+                path: None,
+            },
+            sources: Vec::new(),
+            env_signing_key: None,
+        }
+    }
+
+    fn read(path: &Path) -> Result<Config, TrustChainError> {
+        debug!("reading config file {:?}", path);
+        let s = io_guarded!(fs::read_to_string(path), Io, "error reading config file {:?}", path);
+        Ok(io_guarded!(toml::from_str(&s), Config, "error parsing config file {:?}", path))
+    }
+
+    /// Reads a project's config file, which may only contain the settings in `ProjectConfig`
+    fn read_project(path: &Path) -> Result<Config, TrustChainError> {
+        debug!("reading project config file {:?}", path);
+        let s = io_guarded!(fs::read_to_string(path), Io, "error reading config file {:?}", path);
+        let project: ProjectConfig = io_guarded!(toml::from_str(&s), Config, "error parsing project config file {:?} - it may only configure repositories", path);
+        Ok(Config {
+            repositories: project.repositories,
+            ..Config::default()
+        })
+    }
+
+    /// Overrides all settings that are present in `other`
+    fn merge(&mut self, other: Config) {
+        override_with(&mut self.registry.path, other.registry.path);
+        override_with(&mut self.registry.max_claim_size, other.registry.max_claim_size);
+
+        override_with(&mut self.repositories.maven, other.repositories.maven);
+        override_with(&mut self.repositories.npm, other.repositories.npm);
+        override_with(&mut self.repositories.node_modules, other.repositories.node_modules);
+        override_with(&mut self.repositories.cargo, other.repositories.cargo);
+        override_with(&mut self.repositories.local, other.repositories.local);
+        override_with(&mut self.repositories.oci, other.repositories.oci);
+        override_with(&mut self.repositories.python, other.repositories.python);
+
+        override_with(&mut self.maven.executable, other.maven.executable);
+
+        override_with(&mut self.signing.backend, other.signing.backend);
+        override_with(&mut self.signing.hash_algorithm, other.signing.hash_algorithm);
+
+        override_with(&mut self.gpg.executable, other.gpg.executable);
+        override_with(&mut self.gpg.homedir, other.gpg.homedir);
+        override_with(&mut self.gpg.keyring, other.gpg.keyring);
+        override_with(&mut self.gpg.signing_key, other.gpg.signing_key);
+
+        override_with(&mut self.ed25519.key_file, other.ed25519.key_file);
+
+        override_with(&mut self.ssh.executable, other.ssh.executable);
+        override_with(&mut self.ssh.key_file, other.ssh.key_file);
+        override_with(&mut self.minisign.public_keys, other.minisign.public_keys);
+
+        override_with(&mut self.trust.store, other.trust.store);
+        override_with(&mut self.policy.path, other.policy.path);
+    }
+
+    fn apply_env(&mut self) -> Result<(), TrustChainError> {
+        override_with(&mut self.registry.path, env_var("REGISTRY").map(PathBuf::from));
+        if let Some(s) = env_var("MAX_CLAIM_SIZE") {
+            self.registry.max_claim_size = Some(io_guarded!(s.parse(), Config, "invalid value {:?} for {}MAX_CLAIM_SIZE", &s, ENV_PREFIX));
+        }
+
+        override_with(&mut self.repositories.maven, env_var("MAVEN_REPOSITORY").map(PathBuf::from));
+        override_with(&mut self.repositories.npm, env_var("NPM_CACHE").map(PathBuf::from));
+        override_with(&mut self.repositories.node_modules, env_var("NODE_MODULES").map(PathBuf::from));
+        override_with(&mut self.repositories.cargo, env_var("CARGO_HOME").map(PathBuf::from));
+        override_with(&mut self.repositories.local, env_var("LOCAL_ROOT").map(PathBuf::from));
+        override_with(&mut self.repositories.oci, env_var("OCI_LAYOUT").map(PathBuf::from));
+        override_with(&mut self.repositories.python, env_var("PYTHON_REPOSITORY").map(PathBuf::from));
+
+        override_with(&mut self.maven.executable, env_var("MVN").map(PathBuf::from));
+
+        if let Some(s) = env_var("SIGNATURE_BACKEND") {
+            self.signing.backend = Some(s.parse()?);
+        }
+        if let Some(s) = env_var("HASH_ALGORITHM") {
+            self.signing.hash_algorithm = Some(s.parse()?);
+        }
+
+        override_with(&mut self.gpg.executable, env_var("GPG").map(PathBuf::from));
+        override_with(&mut self.gpg.homedir, env_var("GPG_HOMEDIR").map(PathBuf::from));
+        override_with(&mut self.gpg.keyring, env_var("KEYRING").map(PathBuf::from));
+        override_with(&mut self.ed25519.key_file, env_var("ED25519_KEY_FILE").map(PathBuf::from));
+        override_with(&mut self.ssh.executable, env_var("SSH_KEYGEN").map(PathBuf::from));
+        override_with(&mut self.ssh.key_file, env_var("SSH_KEY_FILE").map(PathBuf::from));
+        self.env_signing_key = env_var("SIGNING_KEY");
+
+        override_with(&mut self.trust.store, env_var("TRUST_STORE").map(PathBuf::from));
+        override_with(&mut self.policy.path, env_var("POLICY").map(PathBuf::from));
+
+        Ok(())
+    }
+
+    pub fn registry_path(&self) -> Result<&Path, TrustChainError> {
+        match &self.registry.path {
+            Some(path) => Ok(path),
+            None => err!(Config, "no registry path configured, and there is no home directory to use as a default"),
+        }
+    }
+
+    pub fn trust_store_path(&self) -> Result<&Path, TrustChainError> {
+        match &self.trust.store {
+            Some(path) => Ok(path),
+            None => err!(Config, "no trust store configured, and there is no home directory to use as a default"),
+        }
+    }
+
+    pub fn signature_backend(&self) -> SignatureBackend {
+        self.signing.backend.unwrap_or(SignatureBackend::Gpg)
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.signing.hash_algorithm.unwrap_or(HashAlgorithm::Sha256)
+    }
+
+    /// Sets the signing key for the selected signature backend - for Ed25519 and SSH, this is
+    ///  the key file. `signing_key` is the key from the command line, which takes precedence over
+    ///  the environment. This must be called after the backend is settled.
+    pub fn apply_signing_key(&mut self, signing_key: Option<String>) {
+        let signing_key = match signing_key.or_else(|| self.env_signing_key.take()) {
+            Some(signing_key) => signing_key,
+            None => return,
+        };
+        match self.signature_backend() {
+            SignatureBackend::Gpg => self.gpg.signing_key = Some(signing_key),
+            SignatureBackend::Ed25519 => self.ed25519.key_file = Some(PathBuf::from(signing_key)),
+            SignatureBackend::Ssh => self.ssh.key_file = Some(PathBuf::from(signing_key)),
+        }
+    }
+
+    pub fn max_claim_size(&self) -> u64 {
+        self.registry.max_claim_size.unwrap_or(65536)
+    }
+
+    /// The effective configuration in the config file format
+    pub fn to_toml(&self) -> Result<String, TrustChainError> {
+        Ok(io_guarded!(toml::to_string_pretty(self), Config, "error serializing the configuration"))
+    }
+}
+
+fn override_with<T>(value: &mut Option<T>, new_value: Option<T>) {
+    if new_value.is_some() {
+        *value = new_value;
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(format!("{}{}", ENV_PREFIX, name)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn temp_file(content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("trust-chain-checker-test-{}.toml", Uuid::new_v4()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layers() {
+        let user_file = temp_file(r#"
+            [registry]
+            path = "/user/registry"
+            max-claim-size = 100
+
+            [repositories]
+            maven = "/user/m2"
+            npm = "/user/npm"
+
+            [gpg]
+            homedir = "/user/gnupg"
+        "#);
+        let project_file = temp_file(r#"
+            [repositories]
+            maven = "/project/m2"
+            npm = "/project/npm"
+        "#);
+
+        let config = Config::load_from(None, None).unwrap();
+        assert_eq!(config.max_claim_size(), 65536);
+        assert_eq!(config.gpg.executable, Some(PathBuf::from("gpg")));
+        assert!(config.sources.is_empty());
+
+        // the environment is process wide, so this is the only test that modifies it
+        std::env::set_var("TRUST_CHAIN_CHECKER_REGISTRY", "/env/registry");
+        std::env::set_var("TRUST_CHAIN_CHECKER_NPM_CACHE", "/env/npm");
+        std::env::set_var("TRUST_CHAIN_CHECKER_SIGNATURE_BACKEND", "ed25519");
+        std::env::set_var("TRUST_CHAIN_CHECKER_SIGNING_KEY", "/env/ci.key");
+        std::env::set_var("TRUST_CHAIN_CHECKER_SSH_KEYGEN", "/env/ssh-keygen");
+        let config = Config::load_from(Some(user_file.clone()), Some(project_file.clone()));
+        std::env::set_var("TRUST_CHAIN_CHECKER_MAX_CLAIM_SIZE", "lots");
+        let invalid = Config::load_from(None, None);
+        std::env::remove_var("TRUST_CHAIN_CHECKER_MAX_CLAIM_SIZE");
+        std::env::set_var("TRUST_CHAIN_CHECKER_SIGNATURE_BACKEND", "rsa");
+        let invalid_backend = Config::load_from(None, None);
+        for name in ["REGISTRY", "NPM_CACHE", "SIGNATURE_BACKEND", "SIGNING_KEY", "SSH_KEYGEN"] {
+            std::env::remove_var(format!("{}{}", ENV_PREFIX, name));
+        }
+
+        let mut config = config.unwrap();
+        config.apply_signing_key(None);
+        assert_eq!(config.registry_path().unwrap(), Path::new("/env/registry"));
+        assert_eq!(config.max_claim_size(), 100);
+        assert_eq!(config.repositories.maven, Some(PathBuf::from("/project/m2")));
+        assert_eq!(config.repositories.npm, Some(PathBuf::from("/env/npm")));
+        assert_eq!(config.gpg.homedir, Some(PathBuf::from("/user/gnupg")));
+        assert_eq!(config.gpg.executable, Some(PathBuf::from("gpg")));
+        assert_eq!(config.sources, vec!(user_file, project_file));
+        assert_eq!(config.signature_backend(), SignatureBackend::Ed25519);
+        assert_eq!(config.ed25519.key_file, Some(PathBuf::from("/env/ci.key")));
+        assert_eq!(config.gpg.signing_key, None);
+        assert_eq!(config.ssh.executable, Some(PathBuf::from("/env/ssh-keygen")));
+        assert!(matches!(invalid, Err(TrustChainError { kind: TrustChainErrorKind::Config, .. })));
+        assert!(matches!(invalid_backend, Err(TrustChainError { kind: TrustChainErrorKind::Config, .. })));
+
+        assert!(Config::load_from(Some(std::env::temp_dir().join(format!("{}.toml", Uuid::new_v4()))), None).unwrap().sources.is_empty());
+    }
+
+    #[test]
+    fn test_project_config() {
+        let config = Config::read_project(&temp_file("[repositories]\ncargo = \"/project/cargo\"\n")).unwrap();
+        assert_eq!(config.repositories.cargo, Some(PathBuf::from("/project/cargo")));
+        assert_eq!(config.registry.path, None);
+
+        for content in ["[registry]\npath = \"/project/registry\"\n", "[gpg]\nexecutable = \"/project/gpg\"\n", "[gpg]\nsigning-key = \"ABCD\"\n",
+                "[trust]\nstore = \"/project/trust.toml\"\n", "[signing]\nbackend = \"ed25519\"\n", "[ed25519]\nkey-file = \"/project/ci.key\"\n",
+                "[ssh]\nexecutable = \"/project/ssh-keygen\"\n", "[minisign]\npublic-keys = [\"RWQ\"]\n"] {
+            let path = temp_file(content);
+            assert!(matches!(Config::read_project(&path), Err(TrustChainError { kind: TrustChainErrorKind::Config, .. })), "{}", content);
+            assert!(Config::read(&path).is_ok());
+            assert!(Config::load_from(None, Some(path)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut config = Config::defaults();
+        config.merge(Config {
+            gpg: GpgConfig {
+                executable: None,
+                homedir: Some(PathBuf::from("/gnupg")),
+                keyring: None,
+                signing_key: Some("ABCD".to_string()),
+            },
+            signing: SigningConfig {
+                backend: Some(SignatureBackend::Ed25519),
+                hash_algorithm: Some(HashAlgorithm::Sha512),
+            },
+            ..Config::default()
+        });
+        assert_eq!(config.gpg.executable, Some(PathBuf::from("gpg")));
+        assert_eq!(config.gpg.homedir, Some(PathBuf::from("/gnupg")));
+        assert_eq!(config.gpg.signing_key.as_deref(), Some("ABCD"));
+        assert_eq!(config.max_claim_size(), 65536);
+        assert_eq!(config.signature_backend(), SignatureBackend::Ed25519);
+        assert_eq!(config.hash_algorithm(), HashAlgorithm::Sha512);
+        assert!(config.ed25519.key_file.is_some());
+    }
+
+    #[test]
+    fn test_apply_signing_key() {
+        let mut config = Config::defaults();
+        config.apply_signing_key(None);
+        assert_eq!(config.gpg.signing_key, None);
+
+        // the key goes to the backend that is selected when it is applied
+        config.env_signing_key = Some("ABCD".to_string());
+        config.apply_signing_key(None);
+        assert_eq!(config.gpg.signing_key.as_deref(), Some("ABCD"));
+
+        config.signing.backend = Some(SignatureBackend::Ed25519);
+        config.apply_signing_key(Some("/cli/ci.key".to_string()));
+        assert_eq!(config.ed25519.key_file, Some(PathBuf::from("/cli/ci.key")));
+        assert_eq!(config.gpg.signing_key.as_deref(), Some("ABCD"));
+
+        // the command line takes precedence over the environment
+        config.env_signing_key = Some("/env/ci.key".to_string());
+        config.apply_signing_key(Some("/cli/other.key".to_string()));
+        assert_eq!(config.ed25519.key_file, Some(PathBuf::from("/cli/other.key")));
+
+        config.signing.backend = Some(SignatureBackend::Ssh);
+        config.apply_signing_key(Some("/cli/id_ed25519".to_string()));
+        assert_eq!(config.ssh.key_file, Some(PathBuf::from("/cli/id_ed25519")));
+    }
+
+    #[test]
+    fn test_signature_backend() {
+        assert_eq!("gpg".parse::<SignatureBackend>().unwrap(), SignatureBackend::Gpg);
+        assert_eq!("ed25519".parse::<SignatureBackend>().unwrap(), SignatureBackend::Ed25519);
+        assert_eq!("ssh".parse::<SignatureBackend>().unwrap(), SignatureBackend::Ssh);
+        assert!("GPG".parse::<SignatureBackend>().is_err());
+        assert_eq!(Config::default().signature_backend(), SignatureBackend::Gpg);
+    }
+}
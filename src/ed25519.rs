@@ -0,0 +1,331 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::*;
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+use crate::err::*;
+use crate::signing::{KeyType, PublicKey, SignedFilePath, Signer, Verification, Verifier};
+use crate::util::to_hex_string;
+
+
+const ALGORITHM: &str = "ed25519";
+
+/// prepended to the signed data so that the signatures cannot be confused with signatures
+///  made with the same key for other purposes
+const SIGNATURE_CONTEXT: &[u8] = b"trust-chain-checker ed25519 signature v1\n";
+
+/// A key pair for signing claims without a GnuPG keyring, e.g. by CI bots. Key files are TOML:
+///
+/// ```toml
+/// algorithm = "ed25519"
+/// name = "CI bot <ci@example.org>"
+/// public-key = "<base64 encoded public key>"
+/// secret-key = "<base64 encoded PKCS#8 document>"
+/// ```
+///
+/// The key's fingerprint is the upper case hex SHA-256 of the raw public key, so it can be
+///  derived from the public key alone.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct KeyFile {
+    algorithm: String,
+    /// the signer's name, used as the claims' uid
+    name: String,
+    public_key: String,
+    secret_key: String,
+}
+
+/// Signatures are stored as TOML, with the public key so that they can be verified without a
+///  keyring. Whether the key is trusted is decided by the trust store, based on the fingerprint.
+///
+/// ```toml
+/// algorithm = "ed25519"
+/// name = "CI bot <ci@example.org>"
+/// public-key = "<base64 encoded public key>"
+/// created = 1792262260
+/// signature = "<base64 encoded signature>"
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct SignatureFile {
+    algorithm: String,
+    name: String,
+    public_key: String,
+    /// seconds since the epoch
+    created: u64,
+    signature: String,
+}
+
+impl SignatureFile {
+    /// Returns None if the data is not an Ed25519 signature file
+    fn parse(data: &[u8]) -> Option<SignatureFile> {
+        let s = std::str::from_utf8(data).ok()?;
+        let signature: SignatureFile = toml::from_str(s).ok()?;
+        if signature.algorithm == ALGORITHM { Some(signature) } else { None }
+    }
+}
+
+/// The data that is actually signed: the data file, bound to the signer's name and the creation
+///  time so that these cannot be changed in the signature file
+fn signed_message(name: &str, created: u64, data: &[u8]) -> Vec<u8> {
+    let mut result = SIGNATURE_CONTEXT.to_vec();
+    result.extend_from_slice(&created.to_be_bytes());
+    result.extend_from_slice(&(name.len() as u32).to_be_bytes());
+    result.extend_from_slice(name.as_bytes());
+    result.extend_from_slice(data);
+    result
+}
+
+pub fn fingerprint(public_key: &[u8]) -> String {
+    to_hex_string(digest(&SHA256, public_key).as_ref()).to_uppercase()
+}
+
+/// Generates a new key pair and writes it to a key file, which must not exist yet. Returns
+///  the new key's fingerprint.
+pub fn generate_key_file(path: &Path, name: &str) -> Result<String, TrustChainError> {
+    if path.exists() {
+        return err!(Config, "the key file {:?} already exists", path);
+    }
+
+    let rng = SystemRandom::new();
+    let pkcs8 = match Ed25519KeyPair::generate_pkcs8(&rng) {
+        Ok(pkcs8) => pkcs8,
+        Err(e) => return err!(Generic, "error generating an Ed25519 key: {}", e),
+    };
+    let key_pair = match Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()) {
+        Ok(k) => k,
+        Err(e) => return err!(Generic, "error generating an Ed25519 key: {}", e),
+    };
+
+    let key_file = KeyFile {
+        algorithm: ALGORITHM.to_string(),
+        name: name.to_string(),
+        public_key: base64::encode(key_pair.public_key().as_ref()),
+        secret_key: base64::encode(pkcs8.as_ref()),
+    };
+    let s = io_guarded!(toml::to_string_pretty(&key_file), Generic, "error serializing key file");
+
+    if let Some(folder) = path.parent() {
+        io_guarded!(fs::create_dir_all(folder), Io, "error creating folder {:?} for the key file", folder);
+    }
+    write_secret_file(path, &s)?;
+    Ok(fingerprint(key_pair.public_key().as_ref()))
+}
+
+#[cfg(unix)]
+fn write_secret_file(path: &Path, s: &str) -> Result<(), TrustChainError> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut f = io_guarded!(fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path), Io, "error creating key file {:?}", path);
+    io_guarded!(f.write_all(s.as_bytes()), Io, "error writing key file {:?}", path);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_secret_file(path: &Path, s: &str) -> Result<(), TrustChainError> {
+    io_guarded!(fs::write(path, s), Io, "error writing key file {:?}", path);
+    Ok(())
+}
+
+
+/// Signs claims with the Ed25519 key pair from a key file
+pub struct Ed25519Signer {
+    key_file: PathBuf,
+}
+
+impl Ed25519Signer {
+    /// The key file is only read when it is needed, so that claims can be listed and verified
+    ///  without one
+    pub fn new(key_file: Option<&Path>) -> Result<Ed25519Signer, TrustChainError> {
+        match key_file {
+            Some(key_file) => Ok(Ed25519Signer { key_file: key_file.to_path_buf() }),
+            None => err!(Config, "no Ed25519 key file configured"),
+        }
+    }
+
+    fn load(&self) -> Result<(Ed25519KeyPair, String), TrustChainError> {
+        debug!("reading Ed25519 key file {:?}", &self.key_file);
+        let s = io_guarded!(fs::read_to_string(&self.key_file), Io, "error reading key file {:?}", &self.key_file);
+        let key_file: KeyFile = io_guarded!(toml::from_str(&s), Config, "error parsing key file {:?}", &self.key_file);
+        if key_file.algorithm != ALGORITHM {
+            return err!(Config, "unsupported algorithm {:?} in key file {:?}", &key_file.algorithm, &self.key_file);
+        }
+
+        let pkcs8 = io_guarded!(base64::decode(&key_file.secret_key), Config, "invalid secret key in key file {:?}", &self.key_file);
+        let key_pair = match Ed25519KeyPair::from_pkcs8(&pkcs8) {
+            Ok(k) => k,
+            Err(e) => return err!(Config, "invalid secret key in key file {:?}: {}", &self.key_file, e),
+        };
+        if base64::encode(key_pair.public_key().as_ref()) != key_file.public_key {
+            return err!(Config, "the public key in key file {:?} does not match the secret key", &self.key_file);
+        }
+        Ok((key_pair, key_file.name))
+    }
+}
+
+impl Signer for Ed25519Signer {
+    fn sign(&self, kind_of_file: &str, path: &SignedFilePath) -> Result<(), TrustChainError> {
+        let (key_pair, name) = self.load()?;
+        debug!("signing {} {:?} with key {}", kind_of_file, &path.data_path, fingerprint(key_pair.public_key().as_ref()));
+
+        let data = io_guarded!(fs::read(&path.data_path), Io, "error reading {} {:?}", kind_of_file, &path.data_path);
+        let created = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let signature = key_pair.sign(&signed_message(&name, created, &data));
+
+        let signature_file = SignatureFile {
+            algorithm: ALGORITHM.to_string(),
+            name,
+            public_key: base64::encode(key_pair.public_key().as_ref()),
+            created,
+            signature: base64::encode(signature.as_ref()),
+        };
+        let s = io_guarded!(toml::to_string_pretty(&signature_file), Generic, "error serializing {} signature", kind_of_file);
+        io_guarded!(fs::write(&path.sig_path, s), Io, "error writing {} signature {:?}", kind_of_file, &path.sig_path);
+        Ok(())
+    }
+
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError> {
+        let (key_pair, name) = self.load()?;
+        let mut result = PublicKey::new(KeyType::Ed25519, fingerprint(key_pair.public_key().as_ref()));
+        result.uid = Some(name);
+        Ok(result)
+    }
+}
+
+/// true if the data is an Ed25519 signature file rather than e.g. an OpenPGP signature
+pub fn is_signature_file(data: &[u8]) -> bool {
+    SignatureFile::parse(data).is_some()
+}
+
+/// Verifies Ed25519 signature files. These contain the public key, so no keyring is needed.
+pub struct Ed25519Verifier;
+
+impl Verifier for Ed25519Verifier {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError> {
+        let sig_data = io_guarded!(fs::read(&path.sig_path), Io, "error reading signature {:?}", &path.sig_path);
+        let signature_file = match SignatureFile::parse(&sig_data) {
+            Some(s) => s,
+            None => return err!(Generic, "The signature file {:?} does not contain a valid Ed25519 signature", &path.sig_path),
+        };
+        let (public_key, signature) = match (base64::decode(&signature_file.public_key), base64::decode(&signature_file.signature)) {
+            (Ok(public_key), Ok(signature)) => (public_key, signature),
+            _ => return err!(Generic, "The signature file {:?} does not contain a valid Ed25519 signature", &path.sig_path),
+        };
+
+        let data = io_guarded!(fs::read(&path.data_path), Io, "error reading {:?}", &path.data_path);
+        let message = signed_message(&signature_file.name, signature_file.created, &data);
+        if UnparsedPublicKey::new(&ED25519, &public_key).verify(&message, &signature).is_err() {
+            error!("The signature {:?} is not a valid signature for {:?} - this may indicate an attack", &path.sig_path, &path.data_path);
+            return err!(InvalidSignature, "The signature {:?} is not a valid signature for {:?} - this may indicate an attack", &path.sig_path, &path.data_path);
+        }
+
+        let mut key = PublicKey::new(KeyType::Ed25519, fingerprint(&public_key));
+        key.uid = Some(signature_file.name);
+        Ok(Verification {
+            key,
+            created: Some(UNIX_EPOCH + Duration::from_secs(signature_file.created)),
+            // Ed25519 keys have no expiration - they are withdrawn by removing them from the trust store
+            key_expired: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// the key of RFC 8032 section 7.1, test 1
+    const KEY_FILE: &str = r#"algorithm = "ed25519"
+name = "CI bot <ci@example.org>"
+public-key = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo="
+secret-key = "MFMCAQEwBQYDK2VwBCIEIJ1hsZ3v/VpguoRK9JLsLMREScVpezJpGXA7rAMcrn9goSMDIQDXWpgBgrEKt9VL/tPJZAc6DuFy89qmIyWvAhpo9wdRGg=="
+"#;
+    const FINGERPRINT: &str = "21FE31DFA154A261626BF854046FD2271B7BED4B6ABE45AA58877EF47F9721B9";
+
+    /// a signature of 'hello\n' by KEY_FILE, calculated by an independent implementation
+    const SIGNATURE: &str = r#"algorithm = "ed25519"
+name = "CI bot <ci@example.org>"
+public-key = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo="
+created = 1792266230
+signature = "Mvit8TAAkYYReV8BQHiCJkeT7GOvU6WcuiP0n4a1oL1vQ9MkGokZ1cYPD9xUjx/6oSHNrificxbdQm/LvDTCCw=="
+"#;
+
+    fn temp_folder() -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    fn verify(data: &str, signature: &str) -> Result<Verification, TrustChainError> {
+        let path = SignedFilePath::new(&temp_folder(), "data.txt");
+        fs::write(&path.data_path, data).unwrap();
+        fs::write(&path.sig_path, signature).unwrap();
+        Ed25519Verifier.verify(&path)
+    }
+
+    #[test]
+    fn test_verify() {
+        let verification = verify("hello\n", SIGNATURE).unwrap();
+        assert_eq!(verification.key.fingerprint, FINGERPRINT);
+        assert_eq!(verification.key.uid.as_deref(), Some("CI bot <ci@example.org>"));
+        assert_eq!(verification.created, Some(UNIX_EPOCH + Duration::from_secs(1792266230)));
+
+        // the data, the name and the creation time are signed
+        for (data, signature) in [("hello!\n", SIGNATURE.to_string()),
+                                  ("hello\n", SIGNATURE.replace("CI bot", "CI bat")),
+                                  ("hello\n", SIGNATURE.replace("1792266230", "1792266231"))] {
+            let e = verify(data, &signature).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+        }
+
+        assert!(verify("hello\n", &SIGNATURE.replace("ed25519", "rsa")).is_err());
+        assert!(verify("hello\n", &format!("{}unknown = 1\n", SIGNATURE)).is_err());
+        assert!(verify("hello\n", "-----BEGIN PGP SIGNATURE-----\n").is_err());
+    }
+
+    #[test]
+    fn test_is_signature_file() {
+        assert!(is_signature_file(SIGNATURE.as_bytes()));
+        assert!(!is_signature_file(KEY_FILE.as_bytes()));
+        assert!(!is_signature_file(b"-----BEGIN PGP SIGNATURE-----\n"));
+        assert!(!is_signature_file(&[0x88, 0x75, 0xFF]));
+    }
+
+    #[test]
+    fn test_sign() {
+        let folder = temp_folder();
+        fs::write(folder.join("ci.key"), KEY_FILE).unwrap();
+        let signer = Ed25519Signer::new(Some(&folder.join("ci.key"))).unwrap();
+        assert_eq!(signer.signing_key().unwrap().fingerprint, FINGERPRINT);
+
+        let path = SignedFilePath::new(&folder, "data.txt");
+        fs::write(&path.data_path, "hello\n").unwrap();
+        signer.sign("claim", &path).unwrap();
+        assert_eq!(Ed25519Verifier.verify(&path).unwrap().key.fingerprint, FINGERPRINT);
+
+        // the public key must match the secret key
+        fs::write(folder.join("other.key"), KEY_FILE.replace("11qYAYKx", "11qYAYKy")).unwrap();
+        assert!(Ed25519Signer::new(Some(&folder.join("other.key"))).unwrap().signing_key().is_err());
+    }
+
+    #[test]
+    fn test_generate_key_file() {
+        let path = temp_folder().join("keys/ci.key");
+        let fingerprint = generate_key_file(&path, "CI bot <ci@example.org>").unwrap();
+        assert_eq!(Ed25519Signer::new(Some(&path)).unwrap().signing_key().unwrap().fingerprint, fingerprint);
+        assert!(generate_key_file(&path, "CI bot <ci@example.org>").is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+}
@@ -2,31 +2,45 @@
 #[derive(Debug)]
 #[allow(dead_code)] //TODO apparently rustc does not notice usage in macros - clean this up anyway: which error kinds do we want to distinguish?
 pub enum TrustChainErrorKind {
+    AlreadyRevoked,
     ArtifactFolderReadError,
     ArtifactNotFound,
     ArtifactReadError,
+    CheckFailed,
     ClaimNotFound,
     Claims,
+    Config,
     ExpiredSignature,
     ExpiredKeySignature,
+    ForeignClaim,
     Generic,
     Gpg,
     InvalidArtifactId,
+    InvalidClaim,
     InvalidSignature,
     Io,
+    Revoked,
+    RevokedKey,
 }
 
 #[derive(Debug)]
 pub struct TrustChainError {
+    #[allow(dead_code)]
     pub kind: TrustChainErrorKind,
     pub description: String,
 }
 
+impl std::fmt::Display for TrustChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
 #[macro_export]
 macro_rules! err {
     ($kind: ident, $($args: tt)+) => (
-        Err(TrustChainError {
-            kind: crate::err::TrustChainErrorKind::$kind,
+        Err($crate::err::TrustChainError {
+            kind: $crate::err::TrustChainErrorKind::$kind,
             description: format!($($args)*),
         })
     )
@@ -51,6 +65,15 @@ macro_rules! execute {
             mmm_command.arg($arg);
         )*
 
+        run_command!($kind, $err_msg, mmm_command);
+    })
+}
+
+#[macro_export]
+macro_rules! run_command {
+    ($kind: ident, $err_msg: expr, $command: expr) => ({
+        let mut mmm_command: std::process::Command = $command;
+
         log::debug!("{:?}", &mmm_command);
 
 //TODO differentiated error messages
@@ -61,4 +84,4 @@ macro_rules! execute {
             return err!($kind, "{}", $err_msg);
         }
     })
-}
\ No newline at end of file
+}
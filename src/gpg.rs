@@ -1,100 +1,126 @@
 use crate::err::*;
 
-use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use log::*;
-use std::io::Write;
-use std::process::Output;
+use std::process::{Command, Output};
 
+use crate::config::GpgConfig;
+use crate::signing::{KeyType, PublicKey, SignedFilePath, Signer, Verification, Verifier};
 
-pub struct PublicKey {
-    pub fingerprint: String,
-}
-
-impl PublicKey {
-    pub fn new(fingerprint: String) -> PublicKey {
-        //TODO check length, no blanks, valid format
-        PublicKey {
-            fingerprint
-        }
-    }
-}
 
-/// A pair of paths, one pointing to a data file and the other pointing to the corresponding
-/// signature file
-#[derive(Debug)]
-pub struct SignedFilePath {
-    pub data_path: PathBuf,
-    pub sig_path: PathBuf,
+/// Signing and verification by invoking an external gpg executable
+#[derive(Clone, Debug)]
+pub struct Gpg {
+    executable: PathBuf,
+    homedir: Option<PathBuf>,
+    /// the keyring to verify signatures against - the home directory's keyring if none is configured
+    keyring: Option<PathBuf>,
+    /// the key to sign with - gpg's default key if none is configured
+    signing_key: Option<String>,
 }
 
-impl SignedFilePath {
-    pub fn new(base_path: &Path, data_file_name: &str) -> SignedFilePath {
-        let sig_file_name: &str = &format!("{}.sig", data_file_name);
-
-        let data_path = base_path.join(data_file_name);
-        let sig_path = base_path.join(sig_file_name);
-
-        SignedFilePath {
-            data_path,
-            sig_path,
+impl Gpg {
+    pub fn new(config: &GpgConfig) -> Gpg {
+        Gpg {
+            executable: config.executable.clone().unwrap_or_else(|| PathBuf::from("gpg")),
+            homedir: config.homedir.clone(),
+            keyring: config.keyring.clone(),
+            signing_key: config.signing_key.clone(),
         }
     }
 
-    pub fn temp(data_file_name: &str) -> SignedFilePath {
-        SignedFilePath::new(&std::env::temp_dir(), data_file_name)
-    }
-
-    pub fn copy_to(&self, other: &SignedFilePath, kind_of_file: &str) -> Result<(), TrustChainError> {
-        io_guarded!(fs::copy(&self.data_path, &other.data_path), Io, "error copying {} from {:?} to {:?}", kind_of_file, &self.data_path, &other.data_path);
-        io_guarded!(fs::copy(&self.sig_path, &other.sig_path), Io, "error copying {} signature from {:?} to {:?}", kind_of_file, &self.sig_path, &other.sig_path);
-        Ok(())
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.executable);
+        if let Some(homedir) = &self.homedir {
+            cmd.arg("--homedir");
+            cmd.arg(homedir);
+        }
+        cmd
     }
 
-    pub fn create_data_file(&self, content: &str, kind_of_file: &str) -> Result<(), TrustChainError> {
-        let mut f = io_guarded!(fs::File::create(&self.data_path), Io, "error creating {} file {:?}", kind_of_file, &self.data_path);
-        io_guarded!(f.write_all(content.as_bytes()), Io, "error writing to {} file {:?}", kind_of_file, &self.data_path);
-        Ok(())
+    fn signing_key_description(&self) -> &str {
+        self.signing_key.as_deref().unwrap_or("<default key>")
     }
+}
 
-    pub fn move_to(self, other: &SignedFilePath, kind_of_file: &str) -> Result<(), TrustChainError> {
-        debug!("moving {} {:?} to {:?}", kind_of_file, &self, &other);
-
-        self.copy_to(other, kind_of_file)?;
+impl Signer for Gpg {
+    fn sign(&self, kind_of_file: &str, path: &SignedFilePath) -> Result<(), TrustChainError> {
+        let mut cmd = self.command();
+        cmd.arg("--detach-sign");
+        cmd.arg("--armor");
+        if let Some(signing_key) = &self.signing_key {
+            cmd.arg("--local-user");
+            cmd.arg(signing_key);
+        }
+        cmd.arg("--output");
+        cmd.arg(&path.sig_path);
+        cmd.arg(&path.data_path);
 
-        io_guarded!(fs::remove_file(&self.data_path), Io, "error removing temporary {} file {:?}", kind_of_file, &self.data_path);
-        io_guarded!(fs::remove_file(&self.sig_path), Io, "error removing temporary {} signature file {:?}", kind_of_file, &self.sig_path);
+        run_command!(Gpg, format!("error signing {} in {:?} (signature file {:?})", kind_of_file, &path.data_path, &path.sig_path), cmd);
 
         Ok(())
     }
-}
 
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError> {
+        let mut cmd = self.command();
+        cmd.arg("--with-colons");
+        cmd.arg("--list-secret-keys");
+        if let Some(signing_key) = &self.signing_key {
+            cmd.arg(signing_key);
+        }
+        debug!("{:?}", &cmd);
 
-pub struct Gpg {
-}
+        let out: Output = io_guarded!(cmd.output(), Gpg, "error invoking gpg to look up the signing key {}", self.signing_key_description());
+        if !out.status.success() {
+            return err!(Gpg, "no secret key found for {}", self.signing_key_description());
+        }
 
-impl Gpg {
-    pub fn sign(kind_of_file: &str, path: &SignedFilePath) -> Result<(), TrustChainError> {
-        //TODO make gpg configurable
-        execute!(Gpg, format!("error signing {} in {:?} (signature file {:?})", kind_of_file, &path.data_path, &path.sig_path),
-            "gpg",
-            "--detach-sign",
-            "--armor",
-            "--local-user",
-            "dummy", //TODO make configurable
-            "--output",
-            &path.sig_path,
-            &path.data_path
-        );
+        // sec:u:255:22:6C1298EFFCB624D9:1792260054:::u:::scSC:::+::ed25519:::0:
+        // fpr:::::::::608ED3F630C2E9EA934B96006C1298EFFCB624D9:
+        // grp:::::::::861ED7C2494AB279C8377CD9B93531A2D8FC1944:
+        // uid:u::::1792260054::881FE46E644626D221D1F766F186549A7649467B::dummy <dummy@example.com>::::::::::0:
+        let s = String::from_utf8_lossy(out.stdout.as_slice());
+        let mut in_primary_key = false;
+        let mut key: Option<PublicKey> = None;
+        for line in s.lines() {
+            let parts: Vec<&str> = line.split(':').collect();
+            match parts[0] {
+                "sec" if key.is_none() => in_primary_key = true,
+                "fpr" if in_primary_key && parts.len() > 9 => {
+                    key = Some(PublicKey::new(KeyType::OpenPgp, parts[9].to_string()));
+                    in_primary_key = false;
+                },
+                "uid" if parts.len() > 9 => {
+                    if let Some(key) = &mut key {
+                        if key.uid.is_none() {
+                            key.uid = Some(unescape_colon_listing(parts[9]));
+                        }
+                    }
+                },
+                "sub" | "ssb" | "sec" => break,
+                _ => {},
+            }
+        }
 
-        Ok(())
+        match key {
+            Some(key) => Ok(key),
+            None => err!(Gpg, "no secret key found for {}", self.signing_key_description()),
+        }
     }
+}
 
-    pub fn verify(path: &SignedFilePath) -> Result<PublicKey, TrustChainError> {
+impl Verifier for Gpg {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError> {
         // gpg --status-fd=1 --verify 4851de30-8c4c-41f9-9c14-9f2efaf10cd8.sig 4851de30-8c4c-41f9-9c14-9f2efaf10cd8
 
-        let mut cmd = std::process::Command::new("gpg");
+        let mut cmd = self.command();
+        if let Some(keyring) = &self.keyring {
+            cmd.arg("--no-default-keyring");
+            cmd.arg("--keyring");
+            cmd.arg(keyring);
+        }
         cmd.arg("--status-fd=1");
         cmd.arg("--verify");
         cmd.arg(&path.sig_path);
@@ -105,6 +131,9 @@ impl Gpg {
         let s = String::from_utf8_lossy(out.stdout.as_slice());
 
         let mut sig_key = None;
+        let mut sig_uid = None;
+        let mut created = None;
+        let mut key_expired = false;
 
         for line in s.lines() {
             debug!("{}", line);
@@ -115,15 +144,23 @@ impl Gpg {
                         // details about a valid signature - the primary key fingerprint in particular
                         // [GNUPG:] VALIDSIG B366A38296498FB36B1A44C56408C89E4018270C 2019-12-01 1575225882 0 4 0 1 10 00 5B8F59E68DA74387B3AB2761DBD433FD3D20D8F1
                         if parts.len() >= 12 {
-                            sig_key = Some(PublicKey::new(parts[11].to_string()));
+                            sig_key = Some(PublicKey::new(KeyType::OpenPgp, parts[11].to_string()));
                         }
                         else {
-                            sig_key = Some(PublicKey::new(parts[2].to_string()));
+                            sig_key = Some(PublicKey::new(KeyType::OpenPgp, parts[2].to_string()));
                         }
+                        // the timestamp is seconds since the epoch or ISO 8601, which is not used here
+                        created = parts.get(4)
+                            .and_then(|t| t.parse().ok())
+                            .map(|t| UNIX_EPOCH + Duration::from_secs(t));
                     },
                     "GOODSIG" => {
                         // no problems with the signature
+                        // [GNUPG:] GOODSIG 6C1298EFFCB624D9 dummy <dummy@example.com>
                         debug!("good signature {:?} for {:?}", &path.sig_path, &path.data_path);
+                        if parts.len() > 3 {
+                            sig_uid = Some(unescape_status(&parts[3..].join(" ")));
+                        }
                     },
                     "BADSIG" => {
                         // trouble! The signature does not match the document and / or the key!
@@ -137,10 +174,13 @@ impl Gpg {
                     "EXPKEYSIG" => {
                         // the key used for the signature is expired
                         debug!("good signature {:?} for {:?}, but the key is expired", &path.sig_path, &path.data_path);
+                        key_expired = true;
+                        if parts.len() > 3 {
+                            sig_uid = Some(unescape_status(&parts[3..].join(" ")));
+                        }
                     },
                     "REVKEYSIG" => {
                         // the key used for the signature was revoked
-                        //TODO is this the best way to handle this?
                         let mut key_id = "???";
                         let mut uid = "???";
                         if parts.len() >= 4 {
@@ -148,8 +188,8 @@ impl Gpg {
                             uid = parts[3];
                         }
 
-                        error!("The signature {:?} is valid for {:?}, but the key {:?} for uid {:?} is expired", &path.sig_path, &path.data_path, key_id, uid);
-                        return err!(ExpiredKeySignature, "The signature {:?} is valid for {:?}, but the key {:?} for uid {:?} is expired", &path.sig_path, &path.data_path, key_id, uid);
+                        error!("The signature {:?} is valid for {:?}, but the key {:?} for uid {:?} is revoked", &path.sig_path, &path.data_path, key_id, uid);
+                        return err!(RevokedKey, "The signature {:?} is valid for {:?}, but the key {:?} for uid {:?} is revoked", &path.sig_path, &path.data_path, key_id, uid);
                     },
                     "ERRSIG" => {
                         // there is a format error in the signature
@@ -162,7 +202,14 @@ impl Gpg {
         }
 
         match sig_key {
-            Some(key) => Ok(key),
+            Some(mut key) => {
+                key.uid = sig_uid;
+                Ok(Verification {
+                    key,
+                    created: created.filter(|t: &SystemTime| *t > UNIX_EPOCH),
+                    key_expired,
+                })
+            },
             None => {
                 if !out.status.success() {
                     err!(Gpg, "error verifying signature {:?} for {:?}", &path.sig_path, &path.data_path)
@@ -174,3 +221,159 @@ impl Gpg {
         }
     }
 }
+
+/// gpg's '--with-colons' listings escape special characters as '\xNN'
+fn unescape_colon_listing(s: &str) -> String {
+    unescape(s, "\\x")
+}
+
+/// gpg's '--status-fd' output escapes special characters as '%NN'
+fn unescape_status(s: &str) -> String {
+    unescape(s, "%")
+}
+
+fn unescape(s: &str, prefix: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = s;
+    while let Some(idx) = rest.find(prefix) {
+        bytes.extend_from_slice(&rest.as_bytes()[..idx]);
+        let hex_start = idx + prefix.len();
+        match rest.get(hex_start..hex_start+2).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(b) => {
+                bytes.push(b);
+                rest = &rest[hex_start+2..];
+            },
+            None => {
+                bytes.extend_from_slice(prefix.as_bytes());
+                rest = &rest[hex_start..];
+            }
+        }
+    }
+    bytes.extend_from_slice(rest.as_bytes());
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use uuid::Uuid;
+
+    /// A gpg home folder shared by all tests, with the keys 'dummy', 'Mallory' and 'Eve' - the
+    ///  latter has a colon in its uid, which gpg escapes in its listings
+    fn homedir() -> &'static Path {
+        static HOMEDIR: OnceLock<PathBuf> = OnceLock::new();
+        HOMEDIR.get_or_init(|| {
+            let homedir = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+            fs::create_dir_all(&homedir).unwrap();
+            for uid in ["dummy <dummy@example.com>", "Mallory <mallory@example.com>", "Eve: the tester <eve@example.com>"] {
+                let out = Command::new("gpg")
+                    .arg("--homedir").arg(&homedir)
+                    .args(["--batch", "--pinentry-mode", "loopback", "--passphrase", "", "--quick-generate-key", uid, "ed25519", "sign", "never"])
+                    .output().unwrap();
+                assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+            }
+            homedir
+        })
+    }
+
+    /// The configuration for signing with the given test key
+    pub(crate) fn test_gpg_config(signing_key: &str) -> GpgConfig {
+        GpgConfig {
+            executable: None,
+            homedir: Some(homedir().to_path_buf()),
+            keyring: None,
+            signing_key: Some(signing_key.to_string()),
+        }
+    }
+
+    pub(crate) fn test_gpg(signing_key: &str) -> Gpg {
+        Gpg::new(&test_gpg_config(signing_key))
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let key = test_gpg("dummy").signing_key().unwrap();
+        assert_eq!(key.fingerprint.len(), 40);
+        assert_eq!(key.uid.as_deref(), Some("dummy <dummy@example.com>"));
+
+        // a key id or fingerprint selects the same key
+        for signing_key in [&key.fingerprint[24..], &key.fingerprint] {
+            let k = test_gpg(signing_key).signing_key().unwrap();
+            assert_eq!(k.fingerprint, key.fingerprint);
+            assert_eq!(k.uid, key.uid);
+        }
+
+        assert_eq!(test_gpg("Eve").signing_key().unwrap().uid.as_deref(), Some("Eve: the tester <eve@example.com>"));
+        assert_ne!(test_gpg("Mallory").signing_key().unwrap().fingerprint, key.fingerprint);
+
+        let e = test_gpg("nobody").signing_key().unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::Gpg), "{:?}", e);
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let path = SignedFilePath::new(&folder, "claim");
+        path.create_data_file("{}", "claim").unwrap();
+
+        let gpg = test_gpg("Eve");
+        gpg.sign("claim", &path).unwrap();
+        let verification = test_gpg("dummy").verify(&path).unwrap();
+        assert_eq!(verification.key.fingerprint, gpg.signing_key().unwrap().fingerprint);
+        assert_eq!(verification.key.uid.as_deref(), Some("Eve: the tester <eve@example.com>"));
+        assert!(!verification.key_expired);
+        let age = SystemTime::now().duration_since(verification.created.unwrap()).unwrap();
+        assert!(age < Duration::from_secs(60), "{:?}", age);
+
+        path.create_data_file("{ }", "claim").unwrap();
+        let e = gpg.verify(&path).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+    }
+
+    #[test]
+    fn test_keyring() {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let keyring = folder.join("keyring.gpg");
+        let out = Command::new("gpg").arg("--homedir").arg(homedir()).arg("--output").arg(&keyring).args(["--export", "dummy"]).output().unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+        let mut config = test_gpg_config("dummy");
+        config.keyring = Some(keyring);
+        let gpg = Gpg::new(&config);
+
+        let path = SignedFilePath::new(&folder, "claim");
+        path.create_data_file("{}", "claim").unwrap();
+        test_gpg("dummy").sign("claim", &path).unwrap();
+        assert_eq!(gpg.verify(&path).unwrap().key.uid.as_deref(), Some("dummy <dummy@example.com>"));
+
+        // Mallory's key is in the home directory, but not in the keyring
+        let path = SignedFilePath::new(&folder, "other-claim");
+        path.create_data_file("{}", "claim").unwrap();
+        test_gpg("Mallory").sign("claim", &path).unwrap();
+        assert!(test_gpg("dummy").verify(&path).is_ok());
+        assert!(gpg.verify(&path).is_err());
+    }
+
+    #[test]
+    fn test_unescape_colon_listing() {
+        // uid:u::::1792260054::881FE46E644626D221D1F766F186549A7649467B::Eve\x3a the tester <eve@example.com>::::::::::0:
+        assert_eq!(unescape_colon_listing("Eve\\x3a the tester <eve@example.com>"), "Eve: the tester <eve@example.com>");
+        assert_eq!(unescape_colon_listing("J\\xc3\\xbcrgen \\x5cx3a"), "J\u{fc}rgen \\x3a");
+        assert_eq!(unescape_colon_listing("trailing \\x3"), "trailing \\x3");
+        assert_eq!(unescape_colon_listing("no \\xzz hex"), "no \\xzz hex");
+    }
+
+    #[test]
+    fn test_unescape_status() {
+        // [GNUPG:] GOODSIG 6C1298EFFCB624D9 100%25 dummy <dummy@example.com>
+        assert_eq!(unescape_status("100%25 dummy <dummy@example.com>"), "100% dummy <dummy@example.com>");
+        assert_eq!(unescape_status("line%0Abreak"), "line\nbreak");
+        assert_eq!(unescape_status("Eve: the tester <eve@example.com>"), "Eve: the tester <eve@example.com>");
+        assert_eq!(unescape_status("50%"), "50%");
+    }
+}
@@ -2,26 +2,78 @@
 
 mod artifacts;
 mod claim;
+mod claim_kind;
+mod config;
+mod ed25519;
 mod gpg;
+mod minisign;
+mod policy;
+mod project;
+mod signing;
+mod ssh;
+mod trust;
 mod util;
 
 use log::*;
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
 use std::path::PathBuf;
-use crate::artifacts::ArtifactRepository;
+use crate::artifacts::{ArtifactId, ArtifactRepository, HashAlgorithm, hash_path};
 use std::sync::Arc;
-use crate::util::{to_hex_string, write_output};
-use crate::claim::{ClaimRegistry, FileSystemClaimRegistry};
+use crate::util::write_output;
+use crate::claim::{AuthenticatedClaim, ClaimFilter, ClaimRegistry, FileSystemClaimRegistry, PositiveClaimData, RevocationClaimData, find_revocation};
 use crate::util::*;
 use crate::err::*;
-use crate::gpg::PublicKey;
+use crate::config::{Config, SignatureBackend};
+use crate::ed25519::{Ed25519Signer, Ed25519Verifier, generate_key_file};
+use crate::gpg::Gpg;
+use crate::minisign::MinisignVerifier;
+use crate::signing::{MultiFormatVerifier, Signer, Verification, Verifier};
+use crate::ssh::{SshSigner, SshVerifier};
+use crate::policy::Policy;
+use crate::trust::{TrustStore, TrustedKey};
+use crate::project::{DependencyList, cargo_lockfile, check_dependencies, maven_dependency_list, maven_project_dependencies, npm_lockfile, poetry_lockfile, requirements_txt, yarn_lockfile};
 
 #[derive(Debug,StructOpt)]
 #[structopt(about="The cross-language and cross-platform distributed build dependency verification tool")]
 struct CliOpts {
+    /// only needed for identifying artifacts by their id in an artifact repository
     #[structopt(long="repository-kind", possible_values=&RepositoryKind::variants(), case_insensitive=true)]
-    repository_kind: RepositoryKind,
+    repository_kind: Option<RepositoryKind>,
+
+    #[structopt(name="The artifact repository's root folder", long="repository", parse(from_os_str))]
+    repository: Option<PathBuf>,
+
+    #[structopt(name="The claim registry's root folder", long="registry", parse(from_os_str))]
+    registry: Option<PathBuf>,
+
+    #[structopt(name="The signature backend: 'gpg', 'ed25519' or 'ssh'", long="signature-backend")]
+    signature_backend: Option<SignatureBackend>,
+
+    #[structopt(name="The hash algorithm for new claims and the 'hash' command: 'sha256', 'sha512' or 'blake3'", long="hash-algorithm")]
+    hash_algorithm: Option<HashAlgorithm>,
+
+    #[structopt(name="The gpg executable", long="gpg", parse(from_os_str))]
+    gpg: Option<PathBuf>,
+
+    #[structopt(name="The gpg home directory", long="gpg-homedir", parse(from_os_str))]
+    gpg_homedir: Option<PathBuf>,
+
+    #[structopt(name="The keyring file to verify signatures against instead of gpg's default keyring", long="keyring", parse(from_os_str))]
+    keyring: Option<PathBuf>,
+
+    #[structopt(name="The id or fingerprint of the key to sign claims with, or the key file for 'ed25519' and 'ssh'", long="signing-key")]
+    signing_key: Option<String>,
+
+    #[structopt(name="The trust store file", long="trust-store", parse(from_os_str))]
+    trust_store: Option<PathBuf>,
+
+    #[structopt(name="The policy file", long="policy", parse(from_os_str))]
+    policy: Option<PathBuf>,
+
+    /// the effective configuration, i.e. including the command line options
+    #[structopt(skip)]
+    config: Config,
 
     #[structopt(subcommand)]
     command: CliOptsCommand,
@@ -32,29 +84,172 @@ enum CliOptsCommand {
     #[structopt(about="calculate an artifact's hash", )]
     Hash(HashOpts),
 
+    #[structopt(about="check an artifact's claims against the policy", )]
+    Check(CheckOpts),
+
+    #[structopt(about="check all dependencies of a Maven project", )]
+    CheckMaven(CheckMavenOpts),
+
+    #[structopt(about="check all dependencies in a lock file: 'Cargo.lock', 'package-lock.json', 'yarn.lock', 'poetry.lock' or 'requirements*.txt' with hashes", )]
+    CheckLockfile(CheckLockfileOpts),
+
+    #[structopt(about="inspect the configuration", )]
+    Config(ConfigCommand),
+
+    #[structopt(about="generate an Ed25519 key file for the 'ed25519' signature backend", )]
+    GenerateKey(GenerateKeyOpts),
+
     #[structopt(about="sign a claim about an artifact", )]
     Sign(SignOpts),
 
+    #[structopt(about="list all claims about an artifact", )]
+    List(ListOpts),
+
+    #[structopt(about="list all claims signed by a given key - the own key by default", )]
+    MyClaims(MyClaimsOpts),
+
+    #[structopt(about="rewrite the own claims that are stored in a legacy format", )]
+    Migrate,
+
+    #[structopt(about="revoke a claim about an artifact", )]
+    Revoke(RevokeOpts),
+
+    #[structopt(about="manage the trust in signing keys", )]
+    Trust(TrustCommand),
+
     #[structopt(about="verify an artifact", )]
     Verify(VerifyOpts),
 }
 
+#[derive(Debug,StructOpt)]
+enum ConfigCommand {
+    #[structopt(about="print the effective configuration", )]
+    Show,
+}
+
+#[derive(Debug,StructOpt)]
+enum TrustCommand {
+    #[structopt(about="set the trust in a key", )]
+    Set(TrustSetOpts),
+
+    #[structopt(about="remove a key from the trust store", )]
+    Remove(TrustRemoveOpts),
+
+    #[structopt(about="list all keys in the trust store", )]
+    List,
+}
+
+#[derive(Debug,StructOpt)]
+struct TrustSetOpts {
+    #[structopt(name="The key's fingerprint", long="fingerprint")]
+    fingerprint: String,
+
+    #[structopt(name="The trust weight between 0.0 and 1.0", long="weight")]
+    weight: f64,
+
+    #[structopt(name="A claim the trust is restricted to", long="claim-key", number_of_values=1)]
+    claim_keys: Vec<String>,
+
+    #[structopt(name="An artifact name prefix the trust is restricted to", long="artifact-prefix", number_of_values=1)]
+    artifact_prefixes: Vec<String>,
+}
+
+#[derive(Debug,StructOpt)]
+struct TrustRemoveOpts {
+    #[structopt(name="The key's fingerprint", long="fingerprint")]
+    fingerprint: String,
+}
+
+#[derive(Debug,StructOpt)]
+struct CheckMavenOpts {
+    #[structopt(name="The project's pom.xml", long="pom", parse(from_os_str), required_unless="The output of 'mvn dependency:list'", conflicts_with="The output of 'mvn dependency:list'")]
+    pom: Option<PathBuf>,
+
+    #[structopt(name="The output of 'mvn dependency:list'", long="dependency-list", parse(from_os_str))]
+    dependency_list: Option<PathBuf>,
+}
+
+#[derive(Debug,StructOpt)]
+struct CheckLockfileOpts {
+    #[structopt(name="The lock file", parse(from_os_str))]
+    lockfile: PathBuf,
+}
+
+/// An artifact, identified by its id in an artifact repository, as a local file or folder, or
+///  directly by its hash
+#[derive(Debug,StructOpt)]
+struct ArtifactOpts {
+    #[structopt(name="The artifact's identifier", long="artifact", conflicts_with_all=&["A local file or folder", "The artifact's hash, e.g. 'sha512:<hex digest>'"])]
+    artifact_id: Option<String>,
+
+    #[structopt(name="A local file or folder", long="file", parse(from_os_str), conflicts_with="The artifact's hash, e.g. 'sha512:<hex digest>'")]
+    file: Option<PathBuf>,
+
+    #[structopt(name="The artifact's hash, e.g. 'sha512:<hex digest>'", long="hash", alias="artifact-hash")]
+    hash: Option<String>,
+}
+
+#[derive(Debug,StructOpt)]
+struct CheckOpts {
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
+}
+
+#[derive(Debug,StructOpt)]
+struct GenerateKeyOpts {
+    #[structopt(name="The signer's name, e.g. 'CI bot <ci@example.org>'", long="name")]
+    name: String,
+
+    #[structopt(name="The key file to create - the configured Ed25519 key file by default", long="output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
 #[derive(Debug,StructOpt)]
 struct SignOpts {
-    #[structopt(name="The artifact's identifier", long="artifact")]
-    artifact_id: String,
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
 
     #[structopt(name="The claim's identifier", long="claim-key")]
     claim_key: String,
 
     #[structopt(name="The claim's value, if any", long="claim-value")]
     claim_value: Option<String>,
+
+    #[structopt(name="A comment on the claim", long="comment")]
+    comment: Option<String>,
+}
+
+#[derive(Debug,StructOpt)]
+struct ListOpts {
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
+}
+
+#[derive(Debug,StructOpt)]
+struct MyClaimsOpts {
+    #[structopt(name="The signing key's fingerprint", long="fingerprint")]
+    fingerprint: Option<String>,
+
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
+
+    #[structopt(name="The claim's identifier", long="claim-key")]
+    claim_key: Option<String>,
+}
+
+#[derive(Debug,StructOpt)]
+struct RevokeOpts {
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
+
+    #[structopt(name="The id of the claim to be revoked", long="claim-id")]
+    claim_id: String,
 }
 
 #[derive(Debug,StructOpt)]
 struct VerifyOpts {
-    #[structopt(name="The id of the artifact to verify", long="artifact")]
-    artifact_id: String,
+    #[structopt(flatten)]
+    artifact: ArtifactOpts,
 
     #[structopt(name="The file name of the claim to be verified", long="claim-file")]
     claim_file_name: String,
@@ -62,14 +257,17 @@ struct VerifyOpts {
 
 #[derive(Debug,StructOpt)]
 struct HashOpts {
-    #[structopt(name="The artifact's ID", long="artifact-id")]
-    artifact_id: String,
+    #[structopt(name="The artifact's ID", long="artifact-id", required_unless="A file or folder to hash", conflicts_with="A file or folder to hash")]
+    artifact_id: Option<String>,
+
+    #[structopt(name="A file or folder to hash", long="path", alias="file", parse(from_os_str))]
+    path: Option<PathBuf>,
 }
 
 arg_enum! {
   #[derive(Debug)]
   enum RepositoryKind {
-    Maven, Npm, Cargo,
+    Maven, Npm, NpmModules, Cargo, CargoSrc, Local, Oci, Python,
   }
 }
 
@@ -77,18 +275,48 @@ arg_enum! {
 fn main() -> Result<(), crate::err::TrustChainError> {
     env_logger::init(); // levels controlled by RUST_LOG env variable
 
-    let cli_opts = CliOpts::from_args();
+    let mut cli_opts = CliOpts::from_args();
+    cli_opts.config = effective_config(&cli_opts, Config::load()?)?;
     debug!("{:?}", cli_opts);
 
     match &cli_opts.command {
         CliOptsCommand::Hash(hash_opts) => do_hash(&cli_opts, hash_opts)?,
+        CliOptsCommand::Check(check_opts) => do_check(&cli_opts, check_opts)?,
+        CliOptsCommand::CheckMaven(check_maven_opts) => do_check_maven(&cli_opts, check_maven_opts)?,
+        CliOptsCommand::CheckLockfile(check_lockfile_opts) => do_check_lockfile(&cli_opts, check_lockfile_opts)?,
+        CliOptsCommand::Config(ConfigCommand::Show) => do_config_show(&cli_opts)?,
+        CliOptsCommand::GenerateKey(generate_key_opts) => {
+            let fingerprint = do_generate_key(&cli_opts, generate_key_opts)?;
+            write_output(&format!("fingerprint: {}", fingerprint));
+        },
         CliOptsCommand::Sign(sign_opts) => {
             let claim_id = do_sign(&cli_opts, sign_opts)?;
             write_output(&format!("claim id: {}", claim_id));
         },
+        CliOptsCommand::List(list_opts) => do_list(&cli_opts, list_opts)?,
+        CliOptsCommand::MyClaims(my_claims_opts) => do_my_claims(&cli_opts, my_claims_opts)?,
+        CliOptsCommand::Migrate => {
+            let num_migrated = claim_registry(&cli_opts)?.migrate_claims()?;
+            write_output(&format!("migrated {} claim files", num_migrated));
+        },
+        CliOptsCommand::Revoke(revoke_opts) => {
+            let revocation_id = do_revoke(&cli_opts, revoke_opts)?;
+            write_output(&format!("revocation id: {}", revocation_id));
+        },
+        CliOptsCommand::Trust(trust_command) => do_trust(&cli_opts, trust_command)?,
         CliOptsCommand::Verify(verify_opts) => {
-            let key = do_verify(&cli_opts, &verify_opts)?;
-            write_output(&format!("valid signature by {}", &key.fingerprint));
+            let (verification, trust_level) = do_verify(&cli_opts, verify_opts)?;
+            let mut message = format!("valid {} signature by {}", verification.key.key_type, &verification.key.fingerprint);
+            if let Some(created) = verification.created {
+                message.push_str(&format!(", made at {}", humantime::format_rfc3339_seconds(created)));
+            }
+            if verification.key_expired {
+                message.push_str(", the key has expired since");
+            }
+            if let Some(trust_level) = trust_level {
+                message.push_str(&format!(", trust level {}", trust_level));
+            }
+            write_output(&message);
         },
     }
 
@@ -96,53 +324,583 @@ fn main() -> Result<(), crate::err::TrustChainError> {
 }
 
 fn do_hash(cli_opts: &CliOpts, hash_opts: &HashOpts) -> Result<(), TrustChainError> {
-    debug!("calculating hash for {}", hash_opts.artifact_id);
+    debug!("calculating hash: {:?}", hash_opts);
+
+    let algorithm = cli_opts.config.hash_algorithm();
+    let hash = match (&hash_opts.artifact_id, &hash_opts.path) {
+        (Some(artifact_id), _) => artifact_repository(cli_opts)?.do_hash(artifact_id, algorithm)?,
+        (None, Some(path)) => hash_path(path, &[algorithm])?.remove(0),
+        (None, None) => return err!(InvalidArtifactId, "either an artifact id or a path is required"),
+    };
+
+    write_output(&hash.to_string());
+    Ok(())
+}
+
+fn do_check_maven(cli_opts: &CliOpts, check_maven_opts: &CheckMavenOpts) -> Result<(), TrustChainError> {
+    debug!("checking Maven project: {:?}", check_maven_opts);
+
+    let maven_root = repository_root(&cli_opts.config.repositories.maven, "maven")?;
+    let dependencies = match (&check_maven_opts.pom, &check_maven_opts.dependency_list) {
+        (Some(pom), _) => {
+            let mvn = cli_opts.config.maven.executable.clone().unwrap_or_else(|| PathBuf::from("mvn"));
+            maven_project_dependencies(&mvn, pom, &maven_root)?
+        }
+        (None, Some(dependency_list)) => maven_dependency_list(dependency_list)?,
+        (None, None) => return err!(Generic, "either a pom.xml or a dependency list is required"),
+    };
+
+    check_project(cli_opts, &ArtifactRepository::new_maven(maven_root), &dependencies)
+}
+
+fn do_check_lockfile(cli_opts: &CliOpts, check_lockfile_opts: &CheckLockfileOpts) -> Result<(), TrustChainError> {
+    debug!("checking lock file: {:?}", check_lockfile_opts);
+
+    let lockfile = &check_lockfile_opts.lockfile;
+    let file_name = lockfile.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (artifact_repository, dependencies) = match file_name.as_str() {
+        "Cargo.lock" => (ArtifactRepository::new_cargo(repository_root(&cli_opts.config.repositories.cargo, "cargo")?, false), cargo_lockfile(lockfile)?),
+        "package-lock.json" | "npm-shrinkwrap.json" => (ArtifactRepository::new_npm(repository_root(&cli_opts.config.repositories.npm, "npm")?, false), npm_lockfile(lockfile)?),
+        "yarn.lock" => (ArtifactRepository::new_npm(repository_root(&cli_opts.config.repositories.npm, "npm")?, false), yarn_lockfile(lockfile)?),
+        "poetry.lock" => {
+            let python_root = repository_root(&cli_opts.config.repositories.python, "python")?;
+            let dependencies = poetry_lockfile(lockfile, &python_root)?;
+            (ArtifactRepository::new_python(python_root), dependencies)
+        }
+        name if name.starts_with("requirements") && name.ends_with(".txt") => {
+            let python_root = repository_root(&cli_opts.config.repositories.python, "python")?;
+            let dependencies = requirements_txt(lockfile, &python_root)?;
+            (ArtifactRepository::new_python(python_root), dependencies)
+        }
+        _ => return err!(Generic, "unsupported kind of lock file {:?}", lockfile),
+    };
+
+    check_project(cli_opts, &artifact_repository, &dependencies)
+}
+
+/// Prints a report for each dependency and fails if any of them did not pass
+fn check_project(cli_opts: &CliOpts, artifact_repository: &ArtifactRepository, dependencies: &DependencyList) -> Result<(), TrustChainError> {
+    let policy = match &cli_opts.config.policy.path {
+        Some(path) => Policy::load(path)?,
+        None => return err!(Config, "no policy configured"),
+    };
+
+    let claim_registry = claim_registry(cli_opts)?;
+    let reports = check_dependencies(artifact_repository, claim_registry.as_ref(), &dependencies.dependencies, &policy, &trust_store(cli_opts)?);
+
+    for report in &reports {
+        write_output(&report.to_string());
+    }
+    for skipped in &dependencies.skipped {
+        write_output(&format!("{}: skipped ({})", skipped.name, skipped.reason));
+    }
 
-    let artifact_repository = artifact_repository(&cli_opts);
-    let hash = artifact_repository.do_hash(&hash_opts.artifact_id)?;
-    let hash_string = to_hex_string(hash.as_ref());
+    let num_failed = reports.iter().filter(|r| !r.passed()).count();
+    write_output(&format!("{} dependencies checked, {} failed, {} skipped", reports.len(), num_failed, dependencies.skipped.len()));
 
-    write_output(&hash_string);
+    if num_failed > 0 {
+        return err!(CheckFailed, "{} of {} dependencies failed the check", num_failed, reports.len());
+    }
+    Ok(())
+}
+
+fn do_check(cli_opts: &CliOpts, check_opts: &CheckOpts) -> Result<(), TrustChainError> {
+    debug!("checking artifact: {:?}", check_opts);
+
+    let policy = match &cli_opts.config.policy.path {
+        Some(path) => Policy::load(path)?,
+        None => return err!(Config, "no policy configured"),
+    };
+    let artifact = required_artifact(cli_opts, &check_opts.artifact)?;
+
+    let claims = claim_registry(cli_opts)?.authenticated_claims_for_all(&artifact.ids)?;
+    let findings = policy.evaluate(&claims, &artifact.name, &trust_store(cli_opts)?);
+    let passed = findings.iter().all(|f| f.passed);
+
+    write_output(&format!("{}: {}", artifact.name, if passed { "PASSED" } else { "FAILED" }));
+    for finding in &findings {
+        write_output(&format!("  {}", finding));
+    }
+
+    if !passed {
+        return err!(CheckFailed, "artifact {} does not meet the policy", artifact.name);
+    }
     Ok(())
 }
 
+fn do_config_show(cli_opts: &CliOpts) -> Result<(), TrustChainError> {
+    for source in &cli_opts.config.sources {
+        write_output(&format!("# read from {:?}", source));
+    }
+    write_output(&cli_opts.config.to_toml()?);
+    Ok(())
+}
+
+fn do_generate_key(cli_opts: &CliOpts, generate_key_opts: &GenerateKeyOpts) -> Result<String, TrustChainError> {
+    debug!("generating key: {:?}", generate_key_opts);
+
+    let path = match (&generate_key_opts.output, &cli_opts.config.ed25519.key_file) {
+        (Some(path), _) | (None, Some(path)) => path,
+        (None, None) => return err!(Config, "no Ed25519 key file configured, and there is no home directory to use as a default"),
+    };
+    generate_key_file(path, &generate_key_opts.name)
+}
+
 fn do_sign(cli_opts: &CliOpts, sign_opts: &SignOpts) -> Result<String, TrustChainError> {
     debug!("signing claim: {:?}", sign_opts);
 
-    let artifact_repository = artifact_repository(&cli_opts);
-    let claim_registry = claim_registry(cli_opts);
+    let artifact = required_artifact(cli_opts, &sign_opts.artifact)?;
+    let claim_registry = claim_registry(cli_opts)?;
+
+    let hash = artifact.id_for(cli_opts.config.hash_algorithm())?;
+    claim_registry.sign_claim(&artifact.name, hash, &sign_opts.claim_key, sign_opts.claim_value.derefed(), sign_opts.comment.derefed())
+}
+
+fn do_list(cli_opts: &CliOpts, list_opts: &ListOpts) -> Result<(), TrustChainError> {
+    debug!("listing claims: {:?}", list_opts);
+
+    let artifact = required_artifact(cli_opts, &list_opts.artifact)?;
+
+    let claim_registry = claim_registry(cli_opts)?;
+    let trust_store = trust_store(cli_opts)?;
+    let mut claims = claim_registry.authenticated_claims_for_all(&artifact.ids)?;
+    claims.sort_by_key(|c| c.common_data().timestamp);
+
+    for claim in &claims {
+        if let AuthenticatedClaim::Positive(data) = claim.as_ref() {
+            write_output(&format_claim(data, find_revocation(data, &claims), Some(&artifact.name), &trust_store));
+        }
+    }
+    Ok(())
+}
+
+fn do_my_claims(cli_opts: &CliOpts, my_claims_opts: &MyClaimsOpts) -> Result<(), TrustChainError> {
+    debug!("listing own claims: {:?}", my_claims_opts);
+
+    let claim_registry = claim_registry(cli_opts)?;
+    let fingerprint = match &my_claims_opts.fingerprint {
+        Some(fingerprint) => fingerprint.clone(),
+        None => claim_registry.signing_key()?.fingerprint,
+    };
+    // without an artifact, the claims' artifacts are only known by their hashes
+    let artifact = resolve_artifact(cli_opts, &my_claims_opts.artifact)?;
+    let artifact_name = artifact.as_ref().map(|a| a.name.clone());
+    let filter = ClaimFilter {
+        artifacts: artifact.map(|a| a.ids),
+        claim_key: my_claims_opts.claim_key.clone(),
+    };
+
+    let trust_store = trust_store(cli_opts)?;
+    let mut claims = claim_registry.claims_by_signer(&fingerprint, &filter)?;
+    claims.sort_by_key(|c| c.common_data().timestamp);
+
+    for claim in &claims {
+        if let AuthenticatedClaim::Positive(data) = claim.as_ref() {
+            write_output(&format_claim(data, find_revocation(data, &claims), artifact_name.as_deref(), &trust_store));
+        }
+    }
+    Ok(())
+}
+
+/// An artifact given on the command line
+struct ResolvedArtifact {
+    /// the artifact's name in claims: its id in the artifact repository, the local file's name or
+    ///  the hash
+    name: String,
+    /// the artifact's hashes for all algorithms, or only the hash it was identified by
+    ids: Vec<ArtifactId>,
+}
+
+impl ResolvedArtifact {
+    /// The artifact's id for an algorithm. An artifact identified by its hash has no ids for other
+    ///  algorithms.
+    fn id_for(&self, algorithm: HashAlgorithm) -> Result<&ArtifactId, TrustChainError> {
+        match self.ids.iter().find(|id| id.algorithm == algorithm) {
+            Some(id) => Ok(id),
+            None => err!(InvalidArtifactId, "there is no {} hash for artifact {} - please select the algorithm of the given hash with '--hash-algorithm'", algorithm, self.name),
+        }
+    }
+}
+
+/// Artifacts can be identified by their id in an artifact repository, as a local file or folder,
+///  or directly by their hash, e.g. 'sha512:<hex digest>'
+fn resolve_artifact(cli_opts: &CliOpts, artifact_opts: &ArtifactOpts) -> Result<Option<ResolvedArtifact>, TrustChainError> {
+    let result = match (&artifact_opts.artifact_id, &artifact_opts.file, &artifact_opts.hash) {
+        (Some(artifact_id), _, _) => ResolvedArtifact {
+            name: artifact_id.clone(),
+            ids: artifact_repository(cli_opts)?.do_hash_all(artifact_id)?,
+        },
+        (None, Some(file), _) => ResolvedArtifact {
+            name: file.file_name().unwrap_or(file.as_os_str()).to_string_lossy().to_string(),
+            ids: hash_path(file, &HashAlgorithm::ALL)?,
+        },
+        (None, None, Some(hash)) => {
+            let id: ArtifactId = hash.parse()?;
+            ResolvedArtifact {
+                name: id.to_string(),
+                ids: vec!(id),
+            }
+        },
+        (None, None, None) => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+fn required_artifact(cli_opts: &CliOpts, artifact_opts: &ArtifactOpts) -> Result<ResolvedArtifact, TrustChainError> {
+    match resolve_artifact(cli_opts, artifact_opts)? {
+        Some(artifact) => Ok(artifact),
+        None => err!(InvalidArtifactId, "an artifact id, a file or an artifact hash is required"),
+    }
+}
+
+/// `artifact_name` is the name the artifact was identified by on the command line, if any
+fn format_claim(claim: &PositiveClaimData, revocation: Option<&RevocationClaimData>, artifact_name: Option<&str>, trust_store: &TrustStore) -> String {
+    let common = &claim.common_data;
+
+    let mut result = format!("claim {}\n", common.id);
+    result.push_str(&format!("  artifact {} ({})\n", common.artifact_name, common.artifact_id));
+    match &claim.value {
+        Some(value) => result.push_str(&format!("  {}: {}\n", claim.kind.claim_kind, value)),
+        None => result.push_str(&format!("  {}\n", claim.kind.claim_kind)),
+    }
+    // only OpenPGP binds the uid to the key - an Ed25519 signer may claim to be anybody
+    let unverified = if common.signer.has_verified_uid() { "" } else { ", unverified" };
+    result.push_str(&format!("  signed by {} ({}{}) at {}\n", common.signer.fingerprint, common.uid, unverified, humantime::format_rfc3339_seconds(common.timestamp)));
+    result.push_str(&format!("  trust level: {}\n", trust_store.trust_level(&common.signer.fingerprint, &claim.kind.claim_kind, artifact_name)));
+    if let Some(comment) = &common.comment {
+        result.push_str(&format!("  comment: {}\n", comment));
+    }
+    match revocation {
+        Some(r) => result.push_str(&format!("  status: revoked at {} (revocation {})\n", humantime::format_rfc3339_seconds(r.common_data.timestamp), r.common_data.id)),
+        None => result.push_str("  status: valid\n"),
+    }
+    result
+}
+
+fn do_revoke(cli_opts: &CliOpts, revoke_opts: &RevokeOpts) -> Result<String, TrustChainError> {
+    debug!("revoking claim: {:?}", revoke_opts);
+
+    let artifact = required_artifact(cli_opts, &revoke_opts.artifact)?;
+    let claim_registry = claim_registry(cli_opts)?;
 
-    let hash = artifact_repository.do_hash(&sign_opts.artifact_id)?;
-    claim_registry.sign_claim(&sign_opts.artifact_id, &hash, &sign_opts.claim_key, sign_opts.claim_value.derefed())
+    claim_registry.revoke_claim(&artifact.name, &artifact.ids, &revoke_opts.claim_id)
 }
 
-fn do_verify(cli_opts: &CliOpts, verify_opts: &VerifyOpts) -> Result<PublicKey, TrustChainError> {
+/// Returns the signer's key and, if the claim could be read, its trust level
+fn do_verify(cli_opts: &CliOpts, verify_opts: &VerifyOpts) -> Result<(Verification, Option<f64>), TrustChainError> {
     debug!("verifying claim: {:?}", verify_opts);
 
-    let artifact_repository = artifact_repository(&cli_opts);
-    let claim_registry = claim_registry(cli_opts);
+    let artifact = required_artifact(cli_opts, &verify_opts.artifact)?;
+    let claim_registry = claim_registry(cli_opts)?;
 
-    let artifact_hash = artifact_repository.do_hash(&verify_opts.artifact_id)?;
-    claim_registry.verify_claim(&artifact_hash, &verify_opts.claim_file_name)
+    let verification = claim_registry.verify_claim(&artifact.ids, &verify_opts.claim_file_name)?;
+
+    // the trust level depends on the claim's kind and artifact
+    let trust_store = trust_store(cli_opts)?;
+    let trust_level = claim_registry.authenticated_claims_for_all(&artifact.ids)?.iter()
+        .find_map(|c| match c.as_ref() {
+            AuthenticatedClaim::Positive(data) if data.common_data.id.to_string() == verify_opts.claim_file_name =>
+                Some(trust_store.trust_level(&verification.key.fingerprint, &data.kind.claim_kind, Some(&artifact.name))),
+            _ => None,
+        });
+
+    Ok((verification, trust_level))
 }
 
-fn artifact_repository(cli_opts: &CliOpts) -> Arc<ArtifactRepository> {
-    Arc::new(match &cli_opts.repository_kind {
-        RepositoryKind::Maven => ArtifactRepository::new_maven(PathBuf::from("/home/arno/.m2/repository")), //TODO make path configurable
-        RepositoryKind::Npm => panic!("TODO"),
-        RepositoryKind::Cargo => panic!("TODO"),
-    })
+fn do_trust(cli_opts: &CliOpts, trust_command: &TrustCommand) -> Result<(), TrustChainError> {
+    debug!("trust: {:?}", trust_command);
+
+    let path = cli_opts.config.trust_store_path()?;
+    let mut trust_store = TrustStore::load(path)?;
+
+    match trust_command {
+        TrustCommand::Set(set_opts) => {
+            let key = TrustedKey::new(set_opts.weight, set_opts.claim_keys.clone(), set_opts.artifact_prefixes.clone())?;
+            trust_store.set(&set_opts.fingerprint, key);
+            trust_store.save(path)?;
+        },
+        TrustCommand::Remove(remove_opts) => {
+            if !trust_store.remove(&remove_opts.fingerprint) {
+                return err!(Config, "key {} is not in the trust store {:?}", remove_opts.fingerprint, path);
+            }
+            trust_store.save(path)?;
+        },
+        TrustCommand::List => {
+            for (fingerprint, key) in trust_store.keys() {
+                write_output(&format!("{}: {}", fingerprint, key));
+            }
+        },
+    }
+    Ok(())
 }
 
-fn claim_registry(cli_opts: &CliOpts) -> Arc<dyn ClaimRegistry> {
-    println!("{:?}", cli_opts);
-    //TODO make path configurable
-    //TODO error handling
+fn artifact_repository(cli_opts: &CliOpts) -> Result<Arc<ArtifactRepository>, TrustChainError> {
+    let repository_kind = match &cli_opts.repository_kind {
+        Some(repository_kind) => repository_kind,
+        None => return err!(Config, "a repository kind is required for identifying artifacts by their id"),
+    };
+    let repositories = &cli_opts.config.repositories;
+    Ok(Arc::new(match repository_kind {
+        RepositoryKind::Maven => ArtifactRepository::new_maven(repository_root(&repositories.maven, "maven")?),
+        RepositoryKind::Npm => ArtifactRepository::new_npm(repository_root(&repositories.npm, "npm")?, false),
+        RepositoryKind::NpmModules => ArtifactRepository::new_npm(repository_root(&repositories.node_modules, "node_modules")?, true),
+        RepositoryKind::Cargo => ArtifactRepository::new_cargo(repository_root(&repositories.cargo, "cargo")?, false),
+        RepositoryKind::CargoSrc => ArtifactRepository::new_cargo(repository_root(&repositories.cargo, "cargo")?, true),
+        RepositoryKind::Local => ArtifactRepository::new_local(repository_root(&repositories.local, "local")?),
+        RepositoryKind::Oci => ArtifactRepository::new_oci(repository_root(&repositories.oci, "oci")?),
+        RepositoryKind::Python => ArtifactRepository::new_python(repository_root(&repositories.python, "python")?),
+    }))
+}
 
-    //TODO handle 'no home dir
-    let path = dirs::home_dir().unwrap().join(".trust-chain-checker/registry");
-    Arc::new(FileSystemClaimRegistry::new (path).unwrap())
+fn repository_root(root: &Option<PathBuf>, repository_kind: &str) -> Result<PathBuf, TrustChainError> {
+    match root {
+        Some(root) => Ok(root.clone()),
+        None => err!(Config, "no root folder configured for the {} repository", repository_kind),
+    }
 }
 
+fn claim_registry(cli_opts: &CliOpts) -> Result<Arc<dyn ClaimRegistry>, TrustChainError> {
+    let path = cli_opts.config.registry_path()?;
+    let (signer, verifier) = signature_backend(cli_opts)?;
+    let registry = io_guarded!(FileSystemClaimRegistry::new(path.to_path_buf(), signer, verifier, cli_opts.config.max_claim_size()),
+        Claims, "error initializing the claim registry at {:?}", path);
+    Ok(Arc::new(registry))
+}
 
+type SignatureBackendImpl = (Arc<dyn Signer>, Arc<dyn Verifier>);
+
+/// The signer of the configured backend, and a verifier for all signature formats. OpenPGP
+///  signatures are verified by gpg.
+fn signature_backend(cli_opts: &CliOpts) -> Result<SignatureBackendImpl, TrustChainError> {
+    let config = &cli_opts.config;
+    let gpg = Arc::new(Gpg::new(&config.gpg));
+    let signer: Arc<dyn Signer> = match config.signature_backend() {
+        SignatureBackend::Gpg => gpg.clone(),
+        SignatureBackend::Ed25519 => Arc::new(Ed25519Signer::new(config.ed25519.key_file.as_deref())?),
+        SignatureBackend::Ssh => Arc::new(SshSigner::new(&config.ssh)?),
+    };
+
+    let verifier = MultiFormatVerifier {
+        openpgp: gpg,
+        ed25519: Arc::new(Ed25519Verifier),
+        ssh: Arc::new(SshVerifier),
+        minisign: Arc::new(MinisignVerifier::new(&config.minisign)?),
+    };
+    Ok((signer, Arc::new(verifier)))
+}
+fn trust_store(cli_opts: &CliOpts) -> Result<TrustStore, TrustChainError> {
+    TrustStore::load(cli_opts.config.trust_store_path()?)
+}
+
+/// Applies the command line options on top of the other configuration layers
+fn effective_config(cli_opts: &CliOpts, mut config: Config) -> Result<Config, TrustChainError> {
+
+    if let Some(repository) = &cli_opts.repository {
+        let repositories = &mut config.repositories;
+        match cli_opts.repository_kind {
+            None => return err!(Config, "a repository root folder requires a repository kind"),
+            Some(RepositoryKind::Maven) => repositories.maven = Some(repository.clone()),
+            Some(RepositoryKind::Npm) => repositories.npm = Some(repository.clone()),
+            Some(RepositoryKind::NpmModules) => repositories.node_modules = Some(repository.clone()),
+            Some(RepositoryKind::Cargo) | Some(RepositoryKind::CargoSrc) => repositories.cargo = Some(repository.clone()),
+            Some(RepositoryKind::Local) => repositories.local = Some(repository.clone()),
+            Some(RepositoryKind::Oci) => repositories.oci = Some(repository.clone()),
+            Some(RepositoryKind::Python) => repositories.python = Some(repository.clone()),
+        }
+    }
+    if let Some(registry) = &cli_opts.registry {
+        config.registry.path = Some(registry.clone());
+    }
 
+    if let Some(signature_backend) = cli_opts.signature_backend {
+        config.signing.backend = Some(signature_backend);
+    }
+    if let Some(hash_algorithm) = cli_opts.hash_algorithm {
+        config.signing.hash_algorithm = Some(hash_algorithm);
+    }
+    if let Some(gpg) = &cli_opts.gpg {
+        config.gpg.executable = Some(gpg.clone());
+    }
+    if let Some(gpg_homedir) = &cli_opts.gpg_homedir {
+        config.gpg.homedir = Some(gpg_homedir.clone());
+    }
+    if let Some(keyring) = &cli_opts.keyring {
+        config.gpg.keyring = Some(keyring.clone());
+    }
+
+    if let Some(trust_store) = &cli_opts.trust_store {
+        config.trust.store = Some(trust_store.clone());
+    }
+    if let Some(policy) = &cli_opts.policy {
+        config.policy.path = Some(policy.clone());
+    }
+
+    // after the signature backend is final, since the key's meaning depends on it
+    config.apply_signing_key(cli_opts.signing_key.clone());
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use uuid::Uuid;
+    use crate::claim::CommonClaimData;
+    use crate::claim_kind::ClaimKind;
+    use crate::project::Dependency;
+    use crate::signing::{KeyType, PublicKey};
+
+    fn common_data(comment: Option<&str>, secs: u64) -> CommonClaimData {
+        CommonClaimData {
+            id: Uuid::new_v4(),
+            uid: "Alice <alice@example.com>".to_string(),
+            signer: PublicKey::new(KeyType::OpenPgp, "A1B2C3".to_string()),
+            artifact_id: ArtifactId { algorithm: HashAlgorithm::Sha256, hash: vec!(1, 2, 3), tree: false },
+            artifact_name: "app.jar".to_string(),
+            comment: comment.map(str::to_string),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let user_file = std::env::temp_dir().join(format!("trust-chain-checker-test-{}.toml", Uuid::new_v4()));
+        std::fs::write(&user_file, "[gpg]\nsigning-key = \"ABCD\"\nhomedir = \"/user/gnupg\"\n").unwrap();
+        let config = || Config::load_from(Some(user_file.clone()), None).unwrap();
+
+        let cli_opts = CliOpts::from_iter_safe(&["trust-chain-checker", "--repository-kind", "npm", "--repository", "/cli/npm",
+            "--registry", "/cli/registry", "--signing-key", "EF01", "config", "show"]).unwrap();
+        let effective = effective_config(&cli_opts, config()).unwrap();
+        assert_eq!(effective.registry.path, Some(PathBuf::from("/cli/registry")));
+        assert_eq!(effective.repositories.npm, Some(PathBuf::from("/cli/npm")));
+        assert_eq!(effective.gpg.signing_key.as_deref(), Some("EF01"));
+        assert_eq!(effective.gpg.homedir, Some(PathBuf::from("/user/gnupg")));
+
+        let cli_opts = CliOpts::from_iter_safe(&["trust-chain-checker", "--repository-kind", "npm", "config", "show"]).unwrap();
+        let effective = effective_config(&cli_opts, config()).unwrap();
+        assert_eq!(effective.gpg.signing_key.as_deref(), Some("ABCD"));
+
+        // the signing key goes to the backend selected on the command line
+        let cli_opts = CliOpts::from_iter_safe(&["trust-chain-checker", "--repository-kind", "npm", "--signature-backend", "ed25519",
+            "--signing-key", "/cli/ci.key", "config", "show"]).unwrap();
+        let effective = effective_config(&cli_opts, config()).unwrap();
+        assert_eq!(effective.ed25519.key_file, Some(PathBuf::from("/cli/ci.key")));
+        assert_eq!(effective.gpg.signing_key.as_deref(), Some("ABCD"));
+    }
+
+    #[test]
+    fn test_check_project() {
+        let temp_folder = || std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        let maven_root = temp_folder();
+        let folder = maven_root.join("org").join("foo").join("app").join("1.0");
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("app-1.0.jar"), "app").unwrap();
+        let repository = ArtifactRepository::new_maven(maven_root);
+
+        let mut cli_opts = CliOpts::from_iter_safe(&["trust-chain-checker", "--repository-kind", "maven", "config", "show"]).unwrap();
+        cli_opts.config.registry.path = Some(temp_folder());
+        cli_opts.config.gpg = crate::gpg::tests::test_gpg_config("dummy");
+        cli_opts.config.trust.store = Some(temp_folder());
+        let hash = repository.do_hash("org.foo:app:1.0", HashAlgorithm::Sha256).unwrap();
+        let claim_registry = claim_registry(&cli_opts).unwrap();
+        claim_registry.sign_claim("org.foo:app:1.0", &hash, "reviewed", None, None).unwrap();
+        let mut trust_store = TrustStore::default();
+        trust_store.set(&claim_registry.signing_key().unwrap().fingerprint, TrustedKey::new(1.0, Vec::new(), Vec::new()).unwrap());
+        trust_store.save(cli_opts.config.trust_store_path().unwrap()).unwrap();
+
+        let dependencies = DependencyList {
+            dependencies: vec!(Dependency { artifact_id: "org.foo:app:1.0".to_string(), details: None, expected_digest: None }),
+            skipped: Vec::new(),
+        };
+        let e = check_project(&cli_opts, &repository, &dependencies).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::Config), "{:?}", e);
+
+        let policy_file = temp_folder();
+        std::fs::write(&policy_file, "[[require]]\nclaim = \"reviewed\"\n").unwrap();
+        cli_opts.config.policy.path = Some(policy_file.clone());
+        assert!(check_project(&cli_opts, &repository, &dependencies).is_ok());
+
+        std::fs::write(&policy_file, "[[require]]\nclaim = \"reviewed\"\n\n[[require]]\nclaim = \"tested\"\n").unwrap();
+        let e = check_project(&cli_opts, &repository, &dependencies).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::CheckFailed), "{:?}", e);
+    }
+
+    #[test]
+    fn test_resolve_artifact() {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let file = folder.join("release-1.0.tar.gz");
+        std::fs::write(&file, "release").unwrap();
+
+        let resolve = |args: &[&str]| {
+            let cli_opts = CliOpts::from_iter_safe([&["trust-chain-checker", "list"], args].concat()).unwrap();
+            match &cli_opts.command {
+                CliOptsCommand::List(list_opts) => required_artifact(&cli_opts, &list_opts.artifact),
+                _ => unreachable!(),
+            }
+        };
+
+        let artifact = resolve(&["--file", file.to_str().unwrap()]).unwrap();
+        assert_eq!(artifact.name, "release-1.0.tar.gz");
+        assert_eq!(artifact.ids, hash_path(&file, &HashAlgorithm::ALL).unwrap());
+        assert_eq!(artifact.id_for(HashAlgorithm::Blake3).unwrap().algorithm, HashAlgorithm::Blake3);
+
+        let sha512 = format!("sha512:{}", "ab".repeat(64));
+        let artifact = resolve(&["--hash", &sha512]).unwrap();
+        assert_eq!(artifact.name, sha512);
+        assert_eq!(artifact.ids, vec!(sha512.parse::<ArtifactId>().unwrap()));
+        assert_eq!(artifact.id_for(HashAlgorithm::Sha512).unwrap(), &artifact.ids[0]);
+        let e = artifact.id_for(HashAlgorithm::Sha256).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{:?}", e);
+
+        // a plain hex digest is a SHA-256 hash
+        let artifact = resolve(&["--hash", &"cd".repeat(32)]).unwrap();
+        assert_eq!(artifact.name, format!("sha256:{}", "cd".repeat(32)));
+
+        for hash in ["md5:900150983cd24fb0d6963f7d28e17f72", "sha256:abcd", "sha256:xyz", "app-1.0.jar"] {
+            let e = resolve(&["--hash", hash]).err().unwrap();
+            assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{} {:?}", hash, e);
+        }
+
+        // only artifact ids need a repository
+        let e = resolve(&["--artifact", "org.foo:app:1.0"]).err().unwrap();
+        assert!(matches!(e.kind, TrustChainErrorKind::Config), "{:?}", e);
+
+        let e = resolve(&[]).err().unwrap();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidArtifactId), "{:?}", e);
+    }
+
+    #[test]
+    fn test_format_claim() {
+        let mut trust_store = TrustStore::default();
+        trust_store.set("A1B2C3", TrustedKey::new(0.5, Vec::new(), vec!("org.foo:".to_string())).unwrap());
+
+        let claim = PositiveClaimData {
+            common_data: common_data(Some("looks good"), 1600000000),
+            kind: ClaimKind::new("x-checked"),
+            value: Some("yes".to_string()),
+        };
+        assert_eq!(format_claim(&claim, None, Some("org.foo:app:1.0"), &trust_store), format!(
+            "claim {}\n  artifact app.jar (sha256:010203)\n  x-checked: yes\n  signed by A1B2C3 (Alice <alice@example.com>) at 2020-09-13T12:26:40Z\n  trust level: 0.5\n  comment: looks good\n  status: valid\n",
+            claim.common_data.id));
+
+        let revocation = RevocationClaimData {
+            common_data: common_data(None, 1700000000),
+            revoked_claim_id: claim.common_data.id,
+        };
+        let claim = PositiveClaimData {
+            common_data: common_data(None, 1600000000),
+            kind: ClaimKind::new("reviewed"),
+            value: None,
+        };
+        // the trust is scoped to some artifacts, and the claim's own artifact name does not count
+        assert_eq!(format_claim(&claim, Some(&revocation), None, &trust_store), format!(
+            "claim {}\n  artifact app.jar (sha256:010203)\n  reviewed\n  signed by A1B2C3 (Alice <alice@example.com>) at 2020-09-13T12:26:40Z\n  trust level: 0\n  status: revoked at 2023-11-14T22:13:20Z (revocation {})\n",
+            claim.common_data.id, revocation.common_data.id));
+
+        let mut claim = claim;
+        claim.common_data.signer = PublicKey::new(KeyType::Ed25519, "D4E5F6".to_string());
+        assert!(format_claim(&claim, None, None, &trust_store).contains("  signed by D4E5F6 (Alice <alice@example.com>, unverified) at "));
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+use log::*;
+
+use crate::config::MinisignConfig;
+use crate::err::*;
+use crate::signing::{KeyType, PublicKey, SignedFilePath, Verification, Verifier};
+
+
+/// Verifies minisign signatures. These identify the key only by its id, so the public keys must
+///  be configured. Claims cannot be signed with minisign keys by this tool - a claim file that was
+///  signed with 'minisign -S -m <claim> -x <claim>.sig' can be added to the registry manually.
+pub struct MinisignVerifier {
+    /// the configured keys with their ids as shown by minisign
+    keys: Vec<(String, minisign_verify::PublicKey)>,
+}
+
+impl MinisignVerifier {
+    pub fn new(config: &MinisignConfig) -> Result<MinisignVerifier, TrustChainError> {
+        let mut keys = Vec::new();
+        for encoded in config.public_keys.iter().flatten() {
+            // the public key is the second line of a minisign.pub file
+            let key = match minisign_verify::PublicKey::from_base64(encoded.trim()) {
+                Ok(key) => key,
+                Err(e) => return err!(Config, "invalid minisign public key {:?}: {}", encoded, e),
+            };
+            keys.push((key_id(encoded.trim()).unwrap_or_default(), key));
+        }
+        Ok(MinisignVerifier { keys })
+    }
+}
+
+impl Verifier for MinisignVerifier {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError> {
+        let sig_data = io_guarded!(fs::read_to_string(&path.sig_path), Io, "error reading signature {:?}", &path.sig_path);
+        let signature = match minisign_verify::Signature::decode(&sig_data) {
+            Ok(s) => s,
+            Err(e) => return err!(Generic, "The signature file {:?} does not contain a valid minisign signature: {}", &path.sig_path, e),
+        };
+        let signature_key_id = sig_data.lines().nth(1).and_then(key_id).unwrap_or_default();
+
+        let key = match self.keys.iter().find(|(id, _)| id == &signature_key_id) {
+            Some((_, key)) => key,
+            None => return err!(Config, "the minisign key {} that signed {:?} is not configured", signature_key_id, &path.sig_path),
+        };
+
+        let data = io_guarded!(fs::read(&path.data_path), Io, "error reading {:?}", &path.data_path);
+        if let Err(e) = key.verify(&data, &signature, false) {
+            error!("The signature {:?} is not a valid signature for {:?} - this may indicate an attack", &path.sig_path, &path.data_path);
+            return err!(InvalidSignature, "The signature {:?} is not a valid signature for {:?} - this may indicate an attack ({})", &path.sig_path, &path.data_path, e);
+        }
+
+        // the trusted comment is signed, and minisign puts the signature's creation time there
+        //  by default, e.g. 'timestamp:1792262260	file:claim'
+        let created = signature.trusted_comment().split_whitespace()
+            .find_map(|part| part.strip_prefix("timestamp:"))
+            .and_then(|t| t.parse().ok())
+            .map(|t| UNIX_EPOCH + Duration::from_secs(t));
+
+        Ok(Verification {
+            key: PublicKey::new(KeyType::Minisign, signature_key_id),
+            created,
+            key_expired: false,
+        })
+    }
+}
+
+/// The key id of a base64 encoded public key or signature, formatted like minisign does
+fn key_id(encoded: &str) -> Option<String> {
+    let bytes = base64::decode(encoded.trim()).ok()?;
+    let mut id = [0u8; 8];
+    id.copy_from_slice(bytes.get(2..10)?);
+    Some(format!("{:016X}", u64::from_le_bytes(id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// a key with the id 0807060504030201 and a signature of 'hello\n', calculated by an independent
+    ///  implementation of the minisign format
+    const PUBLIC_KEY: &str = "RWQBAgMEBQYHCAOhB7/zzhC+HXDdGOdLwJln5NYwm6UNXx3chmQSVTG4";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBAgMEBQYHCJW9EkMWxs6buKMtyf9iQstZnl/JVX6bcNnk6YHUtSNV2t+uis8Y6wBHAirrTCY2AaaAft2KdItK60I8DOSfeAk=
+trusted comment: timestamp:1792266230	file:data.txt
+GPH5UuCs34vcNs2+3+PiNdG7NMwoQzUeOxWzYls1O1sL7I7RwvtOc8PuO4CFPPuN/HEaJUAj5lj1SXcZECV7Aw==
+";
+
+    fn verify(public_keys: &[&str], data: &str, signature: &str) -> Result<Verification, TrustChainError> {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let path = SignedFilePath::new(&folder, "data.txt");
+        fs::write(&path.data_path, data).unwrap();
+        fs::write(&path.sig_path, signature).unwrap();
+
+        let config = MinisignConfig { public_keys: Some(public_keys.iter().map(|k| k.to_string()).collect()) };
+        MinisignVerifier::new(&config)?.verify(&path)
+    }
+
+    #[test]
+    fn test_key_id() {
+        assert_eq!(key_id(PUBLIC_KEY).as_deref(), Some("0807060504030201"));
+        assert_eq!(key_id(SIGNATURE.lines().nth(1).unwrap()).as_deref(), Some("0807060504030201"));
+        assert_eq!(key_id("RWQ="), None);
+    }
+
+    #[test]
+    fn test_verify() {
+        let verification = verify(&[PUBLIC_KEY], "hello\n", SIGNATURE).unwrap();
+        assert_eq!(verification.key.fingerprint, "0807060504030201");
+        assert_eq!(verification.created, Some(UNIX_EPOCH + Duration::from_secs(1792266230)));
+
+        let e = verify(&[PUBLIC_KEY], "hello!\n", SIGNATURE).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+
+        // the trusted comment is signed as well
+        let e = verify(&[PUBLIC_KEY], "hello\n", &SIGNATURE.replace("1792266230", "1792266231")).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+
+        let e = verify(&[], "hello\n", SIGNATURE).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::Config), "{:?}", e);
+        assert!(verify(&["RWQ="], "hello\n", SIGNATURE).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use log::*;
+use serde::{Deserialize, Deserializer};
+
+use crate::claim::{AuthenticatedClaim, PositiveClaimData, find_revocation};
+use crate::claim_kind::{ClaimValue, ReviewDepth, WellKnownClaimKind};
+use crate::err::*;
+use crate::trust::TrustStore;
+
+
+/// Declares which claims an artifact needs to be accepted, e.g.
+///
+/// ```toml
+/// [[require]]
+/// claim = "reviewed"
+/// min-signers = 2
+/// min-trust = 1.0
+///
+/// [[require]]
+/// claim = "license"
+/// values = ["MIT", "Apache-2.0"]
+///
+/// [[forbid]]
+/// claim = "x-deprecated"
+/// ```
+///
+/// Only valid, non-revoked claims count, and only if they were signed by a key that is trusted
+///  for them, i.e. with a trust level above 0.0 in the trust store.
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// forbid all negative kinds of claims, e.g. 'vulnerable' and 'malicious'
+    pub forbid_negative: bool,
+    pub require: Vec<Requirement>,
+    pub forbid: Vec<Prohibition>,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            forbid_negative: true,
+            require: Vec::new(),
+            forbid: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Requirement {
+    pub claim: String,
+    /// the number of distinct trusted keys that must have signed the claim
+    #[serde(default = "default_min_signers")]
+    pub min_signers: usize,
+    /// the minimum sum of the signers' trust levels
+    #[serde(default)]
+    pub min_trust: f64,
+    /// if present, only claims with one of these values count
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// for 'reviewed' claims: only reviews of at least this depth count
+    #[serde(default, deserialize_with = "deserialize_review_depth")]
+    pub min_depth: Option<ReviewDepth>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Prohibition {
+    pub claim: String,
+    /// the sum of the signers' trust levels from which on the claim is taken seriously
+    #[serde(default)]
+    pub min_trust: f64,
+}
+
+fn default_min_signers() -> usize {
+    1
+}
+
+fn deserialize_review_depth<'de, D>(deserializer: D) -> Result<Option<ReviewDepth>, D::Error> where D: Deserializer<'de> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map(Some).map_err(|e: TrustChainError| serde::de::Error::custom(e.description))
+}
+
+impl Requirement {
+    /// e.g. "claim 'license' with value MIT or Apache-2.0"
+    fn description(&self) -> String {
+        let mut result = format!("claim '{}'", self.claim);
+        if !self.values.is_empty() {
+            result.push_str(&format!(" with value {}", self.values.join(" or ")));
+        }
+        if let Some(min_depth) = self.min_depth {
+            result.push_str(&format!(" with review depth {} or more", min_depth));
+        }
+        result
+    }
+
+    fn accepts(&self, claim: &PositiveClaimData) -> bool {
+        if !self.values.is_empty() && !claim.value.as_ref().is_some_and(|v| self.values.contains(v)) {
+            return false;
+        }
+        match self.min_depth {
+            Some(min_depth) => match claim.kind.typed_value(claim.value.as_deref()) {
+                ClaimValue::ReviewDepth(depth) => depth >= min_depth,
+                _ => false,
+            },
+            None => true,
+        }
+    }
+}
+
+/// The outcome of evaluating a single rule of a policy
+#[derive(Debug)]
+pub struct PolicyFinding {
+    pub passed: bool,
+    pub description: String,
+}
+
+impl std::fmt::Display for PolicyFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", if self.passed { "ok" } else { "failed" }, self.description)
+    }
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Policy, TrustChainError> {
+        debug!("reading policy {:?}", path);
+        let s = io_guarded!(fs::read_to_string(path), Io, "error reading policy {:?}", path);
+        Ok(io_guarded!(toml::from_str(&s), Config, "error parsing policy {:?}", path))
+    }
+
+    /// Evaluates all rules against the claims about an artifact. `artifact_name` is the name the
+    ///  artifact is checked under, for the artifact scopes of the trust store.
+    pub fn evaluate(&self, claims: &[Arc<AuthenticatedClaim>], artifact_name: &str, trust_store: &TrustStore) -> Vec<PolicyFinding> {
+        let mut result = Vec::new();
+
+        for requirement in &self.require {
+            let signers = trusted_signers(&requirement.claim, claims, artifact_name, trust_store, |c| requirement.accepts(c));
+            let trust = signers.values().fold(0.0, |sum, t| sum + t);
+
+            let passed = signers.len() >= requirement.min_signers && trust >= requirement.min_trust;
+            result.push(PolicyFinding {
+                passed,
+                description: format!("{} requires {} trusted signer(s) with a total trust of {} - found {} with a total trust of {}",
+                                     requirement.description(), requirement.min_signers, requirement.min_trust, signers.len(), trust),
+            });
+        }
+
+        let negative_kinds: Vec<Prohibition> = if self.forbid_negative {
+            WellKnownClaimKind::ALL.iter()
+                .filter(|k| k.is_negative() && !self.forbid.iter().any(|p| p.claim == k.name()))
+                .map(|k| Prohibition { claim: k.name().to_string(), min_trust: 0.0 })
+                .collect()
+        }
+        else {
+            Vec::new()
+        };
+
+        for prohibition in self.forbid.iter().chain(negative_kinds.iter()) {
+            let signers = trusted_signers(&prohibition.claim, claims, artifact_name, trust_store, |_| true);
+            let trust = signers.values().fold(0.0, |sum, t| sum + t);
+
+            let passed = signers.is_empty() || trust < prohibition.min_trust;
+            let description = if signers.is_empty() {
+                format!("claim '{}' is forbidden - there is none", prohibition.claim)
+            }
+            else {
+                let fingerprints: Vec<&str> = signers.keys().map(|s| s.as_str()).collect();
+                format!("claim '{}' is forbidden - it was signed by {} with a total trust of {}", prohibition.claim, fingerprints.join(", "), trust)
+            };
+            result.push(PolicyFinding {
+                passed,
+                description,
+            });
+        }
+
+        result
+    }
+}
+
+/// The distinct keys with a trust level above 0.0 that signed a valid claim of a given kind,
+///  together with their trust levels
+fn trusted_signers<F>(claim_key: &str, claims: &[Arc<AuthenticatedClaim>], artifact_name: &str, trust_store: &TrustStore, accepts: F) -> BTreeMap<String, f64> where F: Fn(&PositiveClaimData) -> bool {
+    let mut result = BTreeMap::new();
+
+    for claim in claims {
+        if let AuthenticatedClaim::Positive(data) = claim.as_ref() {
+            if data.kind.claim_kind != claim_key || find_revocation(data, claims).is_some() || !accepts(data) {
+                continue;
+            }
+
+            let common = &data.common_data;
+            let trust_level = trust_store.trust_level(&common.signer.fingerprint, claim_key, Some(artifact_name));
+            if trust_level > 0.0 {
+                result.insert(common.signer.fingerprint.clone(), trust_level);
+            }
+            else {
+                debug!("ignoring claim {} by untrusted key {}", common.id, common.signer.fingerprint);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::artifacts::{ArtifactId, HashAlgorithm};
+    use crate::claim::{CommonClaimData, RevocationClaimData};
+    use crate::claim_kind::ClaimKind;
+    use crate::signing::{KeyType, PublicKey};
+    use crate::trust::TrustedKey;
+
+    fn common_data(signer: &str) -> CommonClaimData {
+        CommonClaimData {
+            id: Uuid::new_v4(),
+            uid: signer.to_string(),
+            signer: PublicKey::new(KeyType::OpenPgp, signer.to_string()),
+            artifact_id: ArtifactId { algorithm: HashAlgorithm::Sha256, hash: vec!(1; 32), tree: false },
+            artifact_name: "org.foo:bar:1.0".to_string(),
+            comment: None,
+            timestamp: SystemTime::now(),
+        }
+    }
+
+    fn claim(signer: &str, kind: &str, value: Option<&str>) -> Arc<AuthenticatedClaim> {
+        Arc::new(AuthenticatedClaim::Positive(PositiveClaimData {
+            common_data: common_data(signer),
+            kind: ClaimKind::new(kind),
+            value: value.map(str::to_string),
+        }))
+    }
+
+    fn revocation(signer: &str, claim: &AuthenticatedClaim) -> Arc<AuthenticatedClaim> {
+        Arc::new(AuthenticatedClaim::Revocation(RevocationClaimData {
+            common_data: common_data(signer),
+            revoked_claim_id: claim.common_data().id,
+        }))
+    }
+
+    fn trust_store() -> TrustStore {
+        let mut store = TrustStore::default();
+        store.set("AAAA", TrustedKey::new(1.0, Vec::new(), Vec::new()).unwrap());
+        store.set("BBBB", TrustedKey::new(0.5, Vec::new(), Vec::new()).unwrap());
+        store.set("CCCC", TrustedKey::new(1.0, Vec::new(), vec!("org.bar:".to_string())).unwrap());
+        store
+    }
+
+    fn passed(policy: &str, claims: &[Arc<AuthenticatedClaim>]) -> Vec<bool> {
+        let policy: Policy = toml::from_str(policy).unwrap();
+        policy.evaluate(claims, "org.foo:bar:1.0", &trust_store()).iter().map(|f| f.passed).collect()
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy: Policy = toml::from_str(r#"
+            [[require]]
+            claim = "reviewed"
+            min-signers = 2
+            min-trust = 1.5
+            min-depth = "partial"
+
+            [[forbid]]
+            claim = "x-deprecated"
+        "#).unwrap();
+        assert!(policy.forbid_negative);
+        assert_eq!(policy.require[0].min_signers, 2);
+        assert_eq!(policy.require[0].min_trust, 1.5);
+        assert_eq!(policy.require[0].min_depth, Some(ReviewDepth::Partial));
+        assert_eq!(policy.forbid[0].claim, "x-deprecated");
+        assert_eq!(policy.forbid[0].min_trust, 0.0);
+
+        let policy: Policy = toml::from_str("[[require]]\nclaim = \"tested\"").unwrap();
+        assert_eq!(policy.require[0].min_signers, 1);
+        assert!(policy.require[0].values.is_empty());
+        assert_eq!(policy.require[0].min_depth, None);
+
+        for s in ["[[require]]\nclaim = \"reviewed\"\nmin-depth = \"deep\"", "[[require]]\nclaim = \"tested\"\nmin-signer = 2", "forbid-positive = true"] {
+            assert!(toml::from_str::<Policy>(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_requirements() {
+        let policy = "forbid-negative = false\n[[require]]\nclaim = \"tested\"\nmin-signers = 2\nmin-trust = 1.5";
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), claim("BBBB", "tested", None)]), vec!(true));
+        // each signer counts once, untrusted signers and other kinds of claims do not count
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), claim("AAAA", "tested", None)]), vec!(false));
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), claim("DDDD", "tested", None)]), vec!(false));
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), claim("BBBB", "reviewed", None)]), vec!(false));
+        // CCCC is only trusted for other artifacts
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), claim("CCCC", "tested", None)]), vec!(false));
+        assert_eq!(passed("forbid-negative = false\n[[require]]\nclaim = \"tested\"\nmin-signers = 2\nmin-trust = 2.0", &[claim("AAAA", "tested", None), claim("BBBB", "tested", None)]), vec!(false));
+
+        // only revocations by the claim's signer count
+        let tested = claim("BBBB", "tested", None);
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), tested.clone(), revocation("AAAA", &tested)]), vec!(true));
+        assert_eq!(passed(policy, &[claim("AAAA", "tested", None), tested.clone(), revocation("BBBB", &tested)]), vec!(false));
+
+        let policy = "forbid-negative = false\n[[require]]\nclaim = \"license\"\nvalues = [\"MIT\", \"Apache-2.0\"]";
+        assert_eq!(passed(policy, &[claim("AAAA", "license", Some("Apache-2.0"))]), vec!(true));
+        assert_eq!(passed(policy, &[claim("AAAA", "license", Some("GPL-3.0"))]), vec!(false));
+        assert_eq!(passed(policy, &[claim("AAAA", "license", None)]), vec!(false));
+
+        let policy = "forbid-negative = false\n[[require]]\nclaim = \"reviewed\"\nmin-depth = \"partial\"";
+        assert_eq!(passed(policy, &[claim("AAAA", "reviewed", Some("full"))]), vec!(true));
+        assert_eq!(passed(policy, &[claim("AAAA", "reviewed", Some("partial"))]), vec!(true));
+        assert_eq!(passed(policy, &[claim("AAAA", "reviewed", Some("skimmed"))]), vec!(false));
+        assert_eq!(passed(policy, &[claim("AAAA", "reviewed", None)]), vec!(false));
+        assert_eq!(passed(policy, &[claim("AAAA", "reviewed", Some("thorough"))]), vec!(false));
+    }
+
+    #[test]
+    fn test_prohibitions() {
+        // 'vulnerable' and 'malicious' are forbidden by default
+        assert_eq!(passed("", &[]), vec!(true, true));
+        assert_eq!(passed("", &[claim("DDDD", "malicious", None)]), vec!(true, true));
+        assert_eq!(passed("", &[claim("BBBB", "vulnerable", Some("CVE-2021-44228"))]), vec!(false, true));
+        assert!(passed("forbid-negative = false", &[claim("AAAA", "malicious", None)]).is_empty());
+
+        let malicious = claim("BBBB", "malicious", None);
+        assert_eq!(passed("", &[malicious.clone(), revocation("BBBB", &malicious)]), vec!(true, true));
+
+        // an explicit prohibition replaces the default one for the same kind
+        let policy = "[[forbid]]\nclaim = \"malicious\"\nmin-trust = 1.0\n\n[[forbid]]\nclaim = \"x-deprecated\"";
+        assert_eq!(passed(policy, std::slice::from_ref(&malicious)), vec!(true, true, true));
+        assert_eq!(passed(policy, &[malicious, claim("BBBB", "malicious", None), claim("AAAA", "x-deprecated", None)]), vec!(true, false, true));
+        assert_eq!(passed(policy, &[claim("AAAA", "malicious", None)]), vec!(false, true, true));
+    }
+}
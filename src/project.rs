@@ -0,0 +1,1166 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+use log::*;
+use ring::digest::{Algorithm, SHA1_FOR_LEGACY_USE_ONLY, SHA256, SHA384, SHA512};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::artifacts::{ArtifactId, ArtifactRepository, HashAlgorithm, hash_path, normalize_python_name, python_distributions};
+use crate::claim::{AuthenticatedClaim, ClaimRegistry, find_revocation};
+use crate::err::*;
+use crate::policy::Policy;
+use crate::trust::TrustStore;
+use crate::util::{from_hex_string, to_hex_string};
+
+
+/// A project's dependency, identified by its id in an artifact repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub artifact_id: String,
+    /// additional information for the report, e.g. a Maven dependency's scope
+    pub details: Option<String>,
+    /// the artifact's digest as recorded by the build tool, e.g. in a lock file
+    pub expected_digest: Option<ExpectedDigest>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedDigest {
+    pub algorithm: &'static Algorithm,
+    /// the artifact matches if its digest is any of these, e.g. one per file of a Python package
+    pub values: Vec<Vec<u8>>,
+}
+
+impl ExpectedDigest {
+    fn sha256(values: Vec<Vec<u8>>) -> ExpectedDigest {
+        ExpectedDigest {
+            algorithm: &SHA256,
+            values,
+        }
+    }
+
+    /// Parses a Subresource Integrity string like 'sha512-<base64 digest>', picking the strongest
+    ///  of several digests
+    fn from_integrity(integrity: &str) -> Option<ExpectedDigest> {
+        let mut result: Option<(usize, ExpectedDigest)> = None;
+
+        for part in integrity.split_whitespace() {
+            let (algorithm_name, base64_digest) = part.split_once('-')?;
+            // options like in 'sha512-...?foo' are allowed by the spec but have no meaning
+            let base64_digest = base64_digest.split('?').next().unwrap_or_default();
+
+            let (strength, algorithm): (usize, &'static Algorithm) = match algorithm_name {
+                "sha512" => (4, &SHA512),
+                "sha384" => (3, &SHA384),
+                "sha256" => (2, &SHA256),
+                "sha1" => (1, &SHA1_FOR_LEGACY_USE_ONLY),
+                _ => continue,
+            };
+            let value = base64::decode(base64_digest).ok()?;
+
+            let stronger = match &result {
+                Some((s, _)) => *s < strength,
+                None => true,
+            };
+            if stronger {
+                result = Some((strength, ExpectedDigest { algorithm, values: vec!(value) }));
+            }
+        }
+        result.map(|(_, digest)| digest)
+    }
+}
+
+fn algorithm_name(algorithm: &'static Algorithm) -> &'static str {
+    if algorithm == &SHA512 { "sha512" }
+    else if algorithm == &SHA384 { "sha384" }
+    else if algorithm == &SHA256 { "sha256" }
+    else { "sha1" }
+}
+
+/// A dependency that is not checked, e.g. because it is not in an artifact repository
+#[derive(Debug, Clone)]
+pub struct SkippedDependency {
+    pub name: String,
+    pub reason: String,
+}
+
+/// All dependencies of a project
+#[derive(Debug, Default)]
+pub struct DependencyList {
+    pub dependencies: Vec<Dependency>,
+    pub skipped: Vec<SkippedDependency>,
+}
+
+/// The outcome of checking a single dependency
+#[derive(Debug)]
+pub struct DependencyReport {
+    pub dependency: Dependency,
+    /// the artifact's hash for the default algorithm
+    pub hash: Option<ArtifactId>,
+    /// the keys of all valid (i.e. non-revoked) claims about the artifact
+    pub claim_keys: Vec<String>,
+    /// everything that is wrong with the dependency - it passed the check if there is nothing
+    pub problems: Vec<String>,
+}
+
+impl DependencyReport {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl std::fmt::Display for DependencyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.dependency.artifact_id)?;
+        if let Some(details) = &self.dependency.details {
+            write!(f, " ({})", details)?;
+        }
+        writeln!(f, ": {}", if self.passed() { "ok" } else { "FAILED" })?;
+
+        if let Some(hash) = &self.hash {
+            writeln!(f, "  hash: {}", hash)?;
+        }
+        if !self.claim_keys.is_empty() {
+            writeln!(f, "  claims: {}", self.claim_keys.join(", "))?;
+        }
+        for problem in &self.problems {
+            writeln!(f, "  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes each dependency and evaluates the claims about it against the policy
+pub fn check_dependencies(repository: &ArtifactRepository, registry: &dyn ClaimRegistry, dependencies: &[Dependency], policy: &Policy, trust_store: &TrustStore) -> Vec<DependencyReport> {
+    dependencies.iter()
+        .map(|d| check_dependency(repository, registry, d, policy, trust_store))
+        .collect()
+}
+
+fn check_dependency(repository: &ArtifactRepository, registry: &dyn ClaimRegistry, dependency: &Dependency, policy: &Policy, trust_store: &TrustStore) -> DependencyReport {
+    debug!("checking dependency {:?}", dependency);
+
+    let mut report = DependencyReport {
+        dependency: dependency.clone(),
+        hash: None,
+        claim_keys: Vec::new(),
+        problems: Vec::new(),
+    };
+
+    // claims may be about any of the artifact's hashes
+    let hashes = match repository.do_hash_all(&dependency.artifact_id) {
+        Ok(hashes) => hashes,
+        Err(e) => {
+            report.problems.push(format!("error hashing the artifact: {}", e.description));
+            return report;
+        }
+    };
+    report.hash = hashes.iter().find(|h| h.algorithm == HashAlgorithm::Sha256).cloned();
+
+    if let Some(expected) = &dependency.expected_digest {
+        let already_hashed = hashes.iter().find(|h| algorithm_name(expected.algorithm) == h.algorithm.name());
+        let actual = match already_hashed {
+            Some(hash) => Ok(hash.hash.clone()),
+            None => repository.do_hash_with(&dependency.artifact_id, expected.algorithm),
+        };
+
+        match actual {
+            Ok(actual) if expected.values.contains(&actual) => {},
+            Ok(actual) => {
+                let expected_values: Vec<String> = expected.values.iter().map(|v| to_hex_string(v)).collect();
+                warn!("{} mismatch for {}: expected {}, found {}", algorithm_name(expected.algorithm), dependency.artifact_id, expected_values.join(" or "), to_hex_string(&actual));
+                report.problems.push(format!("{} mismatch - the expected digest is {}", algorithm_name(expected.algorithm), expected_values.join(" or ")));
+            },
+            Err(e) => report.problems.push(format!("error hashing the artifact with {}: {}", algorithm_name(expected.algorithm), e.description)),
+        }
+    }
+
+    let claims: Vec<Arc<AuthenticatedClaim>> = match registry.authenticated_claims_for_all(&hashes) {
+        Ok(claims) => claims,
+        Err(e) => {
+            report.problems.push(format!("error reading the claims: {}", e.description));
+            return report;
+        }
+    };
+
+    for claim in &claims {
+        if let AuthenticatedClaim::Positive(data) = claim.as_ref() {
+            if find_revocation(data, &claims).is_none() && !report.claim_keys.contains(&data.kind.claim_kind) {
+                report.claim_keys.push(data.kind.claim_kind.clone());
+            }
+        }
+    }
+    report.claim_keys.sort();
+
+    for finding in policy.evaluate(&claims, &dependency.artifact_id, trust_store) {
+        if !finding.passed {
+            report.problems.push(finding.description);
+        }
+    }
+    report
+}
+
+
+/// Resolves a Maven project's transitive dependencies by running 'mvn dependency:list'. This
+///  also downloads missing artifacts to the local repository so they can be hashed.
+pub fn maven_project_dependencies(mvn: &Path, pom: &Path, maven_repository: &Path) -> Result<DependencyList, TrustChainError> {
+    let output_file = std::env::temp_dir().join(format!("{}.dependencies", Uuid::new_v4()));
+
+    let mut cmd = Command::new(mvn);
+    cmd.arg("--batch-mode");
+    cmd.arg("--quiet");
+    cmd.arg("--file");
+    cmd.arg(pom);
+    cmd.arg(format!("-Dmaven.repo.local={}", maven_repository.to_string_lossy()));
+    cmd.arg(format!("-DoutputFile={}", output_file.to_string_lossy()));
+    cmd.arg("-DappendOutput=true");
+    cmd.arg("dependency:list");
+
+    let result = (|| {
+        run_command!(Generic, format!("error resolving the dependencies of {:?}", pom), cmd);
+        maven_dependency_list(&output_file)
+    })();
+
+    if output_file.exists() {
+        if let Err(e) = fs::remove_file(&output_file) {
+            warn!("error removing temporary file {:?}: {:?}", &output_file, e);
+        }
+    }
+    result
+}
+
+/// Parses the output of 'mvn dependency:list', either its output file or its log, e.g.
+///
+/// ```text
+/// [INFO] The following files have been resolved:
+/// [INFO]    org.slf4j:slf4j-api:jar:1.7.30:compile
+/// [INFO]    junit:junit:jar:tests:4.13:test -- module junit
+/// ```
+pub fn maven_dependency_list(path: &Path) -> Result<DependencyList, TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading dependency list {:?}", path);
+
+    let mut result: Vec<Dependency> = Vec::new();
+    for line in s.lines() {
+        // the log has a prefix, and newer versions of the plugin append module information
+        let line = line.strip_prefix("[INFO]").unwrap_or(line);
+        let line = line.split(" -- ").next().unwrap_or("").trim();
+        let parts: Vec<&str> = line.split(':').collect();
+
+        // group:artifact:type[:classifier]:version:scope
+        let (artifact_id, scope) = match parts.as_slice() {
+            [group, artifact, packaging, version, scope] => (format!("{}:{}:{}:{}", group, artifact, packaging, version), scope),
+            [group, artifact, packaging, classifier, version, scope] => (format!("{}:{}:{}:{}:{}", group, artifact, packaging, classifier, version), scope),
+            _ => {
+                if line.contains(':') && !line.ends_with(':') {
+                    warn!("ignoring line {:?} in dependency list {:?}", line, path);
+                }
+                continue;
+            }
+        };
+
+        let dependency = Dependency {
+            artifact_id,
+            details: Some(scope.to_string()),
+            expected_digest: None,
+        };
+        // with several modules, the same dependency can be listed several times
+        if !result.contains(&dependency) {
+            result.push(dependency);
+        }
+    }
+    Ok(DependencyList {
+        dependencies: result,
+        skipped: Vec::new(),
+    })
+}
+
+
+#[derive(Deserialize)]
+struct CargoLockfile {
+    #[serde(default)]
+    package: Vec<CargoLockfilePackage>,
+    /// lock files before version 2 store the checksums here
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct CargoLockfilePackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+}
+
+/// Reads the packages from a 'Cargo.lock' file. Packages from a registry are identified as
+///  'name:version:registry' for the Cargo repository; path and git dependencies are skipped.
+pub fn cargo_lockfile(path: &Path) -> Result<DependencyList, TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading lock file {:?}", path);
+    let lockfile: CargoLockfile = io_guarded!(toml::from_str(&s), Generic, "error parsing lock file {:?}", path);
+
+    let mut result = DependencyList::default();
+    for package in &lockfile.package {
+        let name = format!("{} {}", package.name, package.version);
+
+        let source = match &package.source {
+            Some(source) => source,
+            None => {
+                result.skipped.push(SkippedDependency { name, reason: "path dependency".to_string() });
+                continue;
+            }
+        };
+        let registry = match cargo_registry(source) {
+            Some(registry) => registry,
+            None => {
+                result.skipped.push(SkippedDependency { name, reason: format!("not from a registry: {}", source) });
+                continue;
+            }
+        };
+
+        let checksum = package.checksum.clone()
+            .or_else(|| lockfile.metadata.get(&format!("checksum {} {} ({})", package.name, package.version, source)).cloned());
+        let expected_digest = match checksum {
+            Some(checksum) => match from_hex_string(&checksum) {
+                Some(hash) if hash.len() == 32 => Some(ExpectedDigest::sha256(vec!(hash))),
+                _ => return err!(Generic, "invalid checksum {:?} for {} in lock file {:?}", checksum, name, path),
+            },
+            None => {
+                warn!("no checksum for {} in lock file {:?}", name, path);
+                None
+            }
+        };
+
+        result.dependencies.push(Dependency {
+            artifact_id: match registry {
+                "" => format!("{}:{}", package.name, package.version),
+                registry => format!("{}:{}:{}", package.name, package.version, registry),
+            },
+            details: None,
+            expected_digest,
+        });
+    }
+    Ok(result)
+}
+
+/// The registry part of a Cargo artifact id for a package's source, i.e. the host name Cargo
+///  uses for the index folder. crates.io may be in either of its index folders (git or sparse), so
+///  its registry is left empty.
+fn cargo_registry(source: &str) -> Option<&str> {
+    let url = source.strip_prefix("registry+").or_else(|| source.strip_prefix("sparse+"))?;
+    if url == "https://github.com/rust-lang/crates.io-index" || url.starts_with("https://index.crates.io") {
+        return Some("");
+    }
+
+    let host = url.split("://").nth(1)?.split(['/', ':']).next()?;
+    Some(host)
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfile {
+    lockfile_version: Option<u64>,
+    packages: Option<BTreeMap<String, NpmLockfilePackage>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NpmLockfilePackage {
+    /// only present if the package is installed under a different name (an alias)
+    name: Option<String>,
+    version: Option<String>,
+    resolved: Option<String>,
+    integrity: Option<String>,
+    #[serde(default)]
+    link: bool,
+    #[serde(default)]
+    in_bundle: bool,
+    #[serde(default)]
+    dev: bool,
+}
+
+/// Reads the packages from a 'package-lock.json' (or 'npm-shrinkwrap.json') file in version 2 or 3,
+///  i.e. with a 'packages' section. Packages are identified as 'name@version' for the npm cache.
+pub fn npm_lockfile(path: &Path) -> Result<DependencyList, TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading lock file {:?}", path);
+    let lockfile: NpmLockfile = io_guarded!(serde_json::from_str(&s), Generic, "error parsing lock file {:?}", path);
+
+    let packages = match lockfile.packages {
+        Some(packages) => packages,
+        None => return err!(Generic, "lock file {:?} has version {:?} - only versions 2 and 3 are supported", path, lockfile.lockfile_version),
+    };
+
+    let mut result = DependencyList::default();
+    let mut seen = HashSet::new();
+    for (location, package) in &packages {
+        // the root project has the location ''
+        if location.is_empty() {
+            continue;
+        }
+
+        // 'node_modules/a/node_modules/@scope/b' is installed as '@scope/b' - other locations
+        //  like 'packages/a' are the project's own workspace packages
+        let installed_name = match location.rfind("node_modules/") {
+            Some(idx) => &location[idx + "node_modules/".len()..],
+            None => {
+                result.skipped.push(SkippedDependency { name: location.clone(), reason: "workspace package".to_string() });
+                continue;
+            }
+        };
+        let name = package.name.as_deref().unwrap_or(installed_name);
+        let version = package.version.as_deref().unwrap_or("?");
+
+        let skip_reason = if package.link {
+            Some("link to a local folder".to_string())
+        }
+        else if package.in_bundle {
+            Some("bundled with its parent package".to_string())
+        }
+        else if package.version.is_none() {
+            Some("no version".to_string())
+        }
+        else if package.resolved.is_none() {
+            Some("not from a registry: no 'resolved' URL".to_string())
+        }
+        else {
+            npm_non_registry_source(package.resolved.as_deref())
+        };
+
+        if let Some(reason) = skip_reason {
+            result.skipped.push(SkippedDependency { name: location.clone(), reason });
+            continue;
+        }
+
+        let artifact_id = format!("{}@{}", name, version);
+        if !seen.insert(artifact_id.clone()) {
+            continue;
+        }
+        result.dependencies.push(Dependency {
+            expected_digest: npm_expected_digest(path, &artifact_id, package.integrity.as_deref()),
+            artifact_id,
+            details: if package.dev { Some("dev".to_string()) } else { None },
+        });
+    }
+    Ok(result)
+}
+
+/// Reads the packages from a 'yarn.lock' file in version 1, e.g.
+///
+/// ```text
+/// "@babel/code-frame@^7.0.0", "@babel/code-frame@^7.10.4":
+///   version "7.10.4"
+///   resolved "https://registry.yarnpkg.com/@babel/code-frame/-/code-frame-7.10.4.tgz#168da1a3..."
+///   integrity sha512-vG6SvB6oYEhvgisZNFRmRCUkLz11c7rp...
+///   dependencies:
+///     "@babel/highlight" "^7.10.4"
+/// ```
+///
+/// The packages are looked up in npm's cache, identified as 'name@version'.
+pub fn yarn_lockfile(path: &Path) -> Result<DependencyList, TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading lock file {:?}", path);
+
+    let mut result = DependencyList::default();
+    let mut seen = HashSet::new();
+
+    let mut add_entry = |name: Option<String>, fields: &BTreeMap<String, String>| -> Result<(), TrustChainError> {
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let version = match fields.get("version") {
+            Some(version) => version,
+            None => return err!(Generic, "no version for {} in lock file {:?}", name, path),
+        };
+
+        let skip_reason = match fields.get("resolved") {
+            Some(resolved) => npm_non_registry_source(Some(resolved)),
+            None => Some("not from a registry: no 'resolved' URL".to_string()),
+        };
+        if let Some(reason) = skip_reason {
+            result.skipped.push(SkippedDependency { name: format!("{}@{}", name, version), reason });
+            return Ok(());
+        }
+
+        let artifact_id = format!("{}@{}", name, version);
+        if seen.insert(artifact_id.clone()) {
+            result.dependencies.push(Dependency {
+                expected_digest: npm_expected_digest(path, &artifact_id, fields.get("integrity").map(|s| s.as_str())),
+                artifact_id,
+                details: None,
+            });
+        }
+        Ok(())
+    };
+
+    let mut name: Option<String> = None;
+    let mut fields = BTreeMap::new();
+    for line in s.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(' ') {
+            // a new entry: a list of specifiers like '"name@range", name@range:'
+            add_entry(name.take(), &fields)?;
+            fields.clear();
+
+            let first_spec = line.trim_end_matches(':').split(", ").next().unwrap_or_default().trim_matches('"');
+            name = match yarn_package_name(first_spec) {
+                Some(n) => Some(n.to_string()),
+                None => return err!(Generic, "invalid entry {:?} in lock file {:?}", line, path),
+            };
+        }
+        else if line.starts_with("  ") && !line.starts_with("   ") {
+            // a field of the current entry - deeper indentation is for the dependencies
+            if let Some((key, value)) = line.trim().split_once(' ') {
+                fields.insert(key.trim_matches('"').to_string(), value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    add_entry(name.take(), &fields)?;
+
+    Ok(result)
+}
+
+/// The package name in a specifier like '@scope/name@^1.0.0' or 'alias@npm:name@^1.0.0'
+fn yarn_package_name(spec: &str) -> Option<&str> {
+    let (name, range) = split_package_spec(spec)?;
+    match range.strip_prefix("npm:") {
+        Some(aliased) if !aliased.is_empty() => Some(split_package_spec(aliased).map_or(aliased, |(name, _)| name)),
+        _ => Some(name),
+    }
+}
+
+/// Splits 'name@range' or '@scope/name@range' into the name and the range
+fn split_package_spec(spec: &str) -> Option<(&str, &str)> {
+    let scope_len = if spec.starts_with('@') { 1 } else { 0 };
+    let idx = spec[scope_len..].find('@')? + scope_len;
+    if idx == scope_len {
+        return None;
+    }
+    Some((&spec[..idx], &spec[idx + 1..]))
+}
+
+/// Packages that were not downloaded from a registry are not in npm's cache as 'name@version'
+fn npm_non_registry_source(resolved: Option<&str>) -> Option<String> {
+    match resolved {
+        Some(resolved) if !(resolved.starts_with("https://") || resolved.starts_with("http://")) => Some(format!("not from a registry: {}", resolved)),
+        _ => None,
+    }
+}
+
+fn npm_expected_digest(lockfile: &Path, artifact_id: &str, integrity: Option<&str>) -> Option<ExpectedDigest> {
+    let integrity = match integrity {
+        Some(integrity) => integrity,
+        None => {
+            warn!("no integrity for {} in lock file {:?}", artifact_id, lockfile);
+            return None;
+        }
+    };
+
+    let result = ExpectedDigest::from_integrity(integrity);
+    if result.is_none() {
+        warn!("unsupported integrity {:?} for {} in lock file {:?}", integrity, artifact_id, lockfile);
+    }
+    result
+}
+
+
+/// Reads the requirements from a 'requirements.txt' file for pip's hash-checking mode, e.g.
+///
+/// ```text
+/// requests==2.31.0 \
+///     --hash=sha256:58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f \
+///     --hash=sha256:942c5a758f98d790eaed1a29cb6eefc7ffb0d1cf7af05c3d2791656dbd6ad1e1
+/// ```
+///
+/// As with pip's '--require-hashes', every requirement must be pinned with '==' and have sha256
+///  hashes. Files included with '-r' are read as well; constraints files and other options are
+///  ignored. The distributions are looked up in a Python repository (see `python_dependency`).
+pub fn requirements_txt(path: &Path, python_repository: &Path) -> Result<DependencyList, TrustChainError> {
+    let mut result = DependencyList::default();
+    read_requirements(path, python_repository, &mut result, &mut HashSet::new())?;
+    Ok(result)
+}
+
+fn read_requirements(path: &Path, python_repository: &Path, result: &mut DependencyList, seen: &mut HashSet<String>) -> Result<(), TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading requirements file {:?}", path);
+
+    let mut lines = Vec::new();
+    let mut continued = String::new();
+    for line in s.lines() {
+        match line.strip_suffix('\\') {
+            Some(part) => {
+                continued.push_str(part);
+                continued.push(' ');
+            }
+            None => {
+                continued.push_str(line);
+                lines.push(std::mem::take(&mut continued));
+            }
+        }
+    }
+    lines.push(continued);
+
+    for line in &lines {
+        // comments start with a '#' at the beginning of a line or after whitespace
+        let line = match line.char_indices().find(|(idx, c)| *c == '#' && (*idx == 0 || line[..*idx].ends_with(char::is_whitespace))) {
+            Some((idx, _)) => &line[..idx],
+            None => line.as_str(),
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let first = match tokens.first() {
+            Some(first) => *first,
+            None => continue,
+        };
+
+        if first.starts_with('-') {
+            if let Some(included) = requirements_option_value(&tokens, "-r", "--requirement") {
+                let included = path.parent().unwrap_or_else(|| Path::new(".")).join(included);
+                read_requirements(&included, python_repository, result, seen)?;
+            }
+            else if let Some(editable) = requirements_option_value(&tokens, "-e", "--editable") {
+                result.skipped.push(SkippedDependency { name: editable.to_string(), reason: "editable requirement".to_string() });
+            }
+            else {
+                debug!("ignoring option {:?} in requirements file {:?}", line.trim(), path);
+            }
+            continue;
+        }
+
+        // spaces are allowed in a requirement, e.g. 'requests == 2.31.0 ; python_version > "3.7"'
+        let spec_end = tokens.iter().position(|t| t.starts_with("--")).unwrap_or(tokens.len());
+        let spec = tokens[..spec_end].concat();
+        let spec = spec.split(';').next().unwrap_or_default();
+
+        if spec.contains(['@', '/', '\\']) {
+            result.skipped.push(SkippedDependency { name: spec.to_string(), reason: "not from a package index".to_string() });
+            continue;
+        }
+        let (name, version) = match spec.split_once("==") {
+            // '===' is an exact string match
+            Some((name, version)) => (name.split('[').next().unwrap_or_default(), version.trim_start_matches('=')),
+            None => return err!(Generic, "requirement {} in requirements file {:?} is not pinned with '=='", spec, path),
+        };
+        if name.is_empty() || version.is_empty() || version.contains([',', '<', '>', '!', '~', '=', '*']) {
+            return err!(Generic, "requirement {} in requirements file {:?} is not pinned to a single version", spec, path);
+        }
+
+        let mut hashes = Vec::new();
+        let mut options = tokens[spec_end..].iter();
+        while let Some(option) = options.next() {
+            let value = match option.strip_prefix("--hash") {
+                Some("") => options.next().copied(),
+                Some(value) => value.strip_prefix('='),
+                None => {
+                    debug!("ignoring option {:?} of {} in requirements file {:?}", option, spec, path);
+                    continue;
+                }
+            };
+            match value.and_then(|v| v.split_once(':')) {
+                Some(("sha256", hex)) => match from_hex_string(hex) {
+                    Some(hash) if hash.len() == 32 => hashes.push(hash),
+                    _ => return err!(Generic, "invalid hash {:?} for {} in requirements file {:?}", hex, spec, path),
+                },
+                Some((algorithm, _)) => warn!("ignoring {} hash for {} in requirements file {:?}", algorithm, spec, path),
+                None => return err!(Generic, "invalid hash option {:?} for {} in requirements file {:?}", option, spec, path),
+            }
+        }
+        if hashes.is_empty() {
+            return err!(Generic, "requirement {} in requirements file {:?} has no sha256 hash", spec, path);
+        }
+
+        if seen.insert(format!("{}=={}", normalize_python_name(name), version)) {
+            result.dependencies.push(python_dependency(python_repository, name, version, hashes)?);
+        }
+    }
+    Ok(())
+}
+
+/// The value of an option line like '-r other.txt', '-rother.txt', '--requirement other.txt' or
+///  '--requirement=other.txt'
+fn requirements_option_value<'a>(tokens: &[&'a str], short: &str, long: &str) -> Option<&'a str> {
+    let first = tokens[0];
+    if first == short || first == long {
+        return tokens.get(1).copied();
+    }
+    first.strip_prefix(long).and_then(|v| v.strip_prefix('='))
+        .or_else(|| first.strip_prefix(short).filter(|v| !v.is_empty() && !v.starts_with('-')))
+}
+
+/// A version of a Python package as the distribution that pip would install from the repository,
+///  i.e. the first one with one of the expected hashes. If none has, the first one is checked so
+///  that the mismatch is reported. Without any distribution, the dependency is identified as
+///  'name==version' and fails as not found.
+fn python_dependency(python_repository: &Path, name: &str, version: &str, hashes: Vec<Vec<u8>>) -> Result<Dependency, TrustChainError> {
+    let distributions = python_distributions(python_repository, name, version)?;
+
+    let mut matching = None;
+    for distribution in &distributions {
+        let hash = hash_path(distribution, &[HashAlgorithm::Sha256])?.remove(0);
+        if hashes.contains(&hash.hash) {
+            matching = Some(distribution);
+            break;
+        }
+    }
+
+    let requirement = format!("{}=={}", name, version);
+    let expected_digest = if hashes.is_empty() { None } else { Some(ExpectedDigest::sha256(hashes)) };
+    Ok(match matching.or(distributions.first()).and_then(|d| d.file_name()) {
+        Some(file_name) => Dependency {
+            artifact_id: file_name.to_string_lossy().to_string(),
+            details: Some(requirement),
+            expected_digest,
+        },
+        None => Dependency {
+            artifact_id: requirement,
+            details: None,
+            expected_digest,
+        },
+    })
+}
+
+
+#[derive(Deserialize)]
+struct PoetryLockfile {
+    #[serde(default)]
+    package: Vec<PoetryLockfilePackage>,
+    #[serde(default)]
+    metadata: PoetryLockfileMetadata,
+}
+
+#[derive(Deserialize)]
+struct PoetryLockfilePackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    files: Vec<PoetryLockfileFile>,
+    source: Option<PoetryLockfileSource>,
+}
+
+#[derive(Deserialize)]
+struct PoetryLockfileSource {
+    #[serde(rename = "type")]
+    source_type: String,
+    url: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct PoetryLockfileMetadata {
+    /// lock files before version 2 store the packages' files here, by package name
+    #[serde(default)]
+    files: BTreeMap<String, Vec<PoetryLockfileFile>>,
+}
+
+#[derive(Deserialize)]
+struct PoetryLockfileFile {
+    file: String,
+    hash: String,
+}
+
+/// Reads the packages from a 'poetry.lock' file. Each package lists its distributions' files with
+///  their hashes, which are checked like the hashes in a 'requirements.txt' file (see
+///  `python_dependency`). Packages from git, local folders or files and URLs are skipped.
+pub fn poetry_lockfile(path: &Path, python_repository: &Path) -> Result<DependencyList, TrustChainError> {
+    let s = io_guarded!(fs::read_to_string(path), Io, "error reading lock file {:?}", path);
+    let lockfile: PoetryLockfile = io_guarded!(toml::from_str(&s), Generic, "error parsing lock file {:?}", path);
+
+    let mut result = DependencyList::default();
+    let mut seen = HashSet::new();
+    for package in &lockfile.package {
+        let name = format!("{}=={}", package.name, package.version);
+
+        // 'legacy' is a package index other than PyPI
+        if let Some(source) = package.source.as_ref().filter(|s| s.source_type != "legacy") {
+            let reason = format!("not from a package index: {}", source.url.as_deref().unwrap_or(&source.source_type));
+            result.skipped.push(SkippedDependency { name, reason });
+            continue;
+        }
+        if !seen.insert(format!("{}=={}", normalize_python_name(&package.name), package.version)) {
+            continue;
+        }
+
+        let files = if package.files.is_empty() {
+            lockfile.metadata.files.iter()
+                .find(|(n, _)| normalize_python_name(n) == normalize_python_name(&package.name))
+                .map(|(_, files)| files.as_slice())
+                .unwrap_or_default()
+        }
+        else {
+            package.files.as_slice()
+        };
+
+        let mut hashes = Vec::new();
+        for file in files {
+            match file.hash.split_once(':') {
+                Some(("sha256", hex)) => match from_hex_string(hex) {
+                    Some(hash) if hash.len() == 32 => hashes.push(hash),
+                    _ => return err!(Generic, "invalid hash {:?} of {} in lock file {:?}", file.hash, file.file, path),
+                },
+                _ => warn!("ignoring hash {:?} of {} in lock file {:?}", file.hash, file.file, path),
+            }
+        }
+        if hashes.is_empty() {
+            warn!("no sha256 hashes for {} in lock file {:?}", name, path);
+        }
+
+        result.dependencies.push(python_dependency(python_repository, &package.name, &package.version, hashes)?);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::claim::FileSystemClaimRegistry;
+    use crate::gpg::tests::test_gpg;
+    use crate::trust::TrustedKey;
+
+    fn temp_folder() -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    /// Writes a file into a new temporary folder, returning the file's path
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = temp_folder().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn dependency(artifact_id: &str) -> Dependency {
+        Dependency {
+            artifact_id: artifact_id.to_string(),
+            details: Some("compile".to_string()),
+            expected_digest: None,
+        }
+    }
+
+    fn artifact_ids(dependencies: &DependencyList) -> Vec<&str> {
+        dependencies.dependencies.iter().map(|d| d.artifact_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_check_dependencies() {
+        let maven_root = temp_folder();
+        for (name, content) in [("app", "app"), ("lib", "lib")] {
+            let folder = maven_root.join("org").join("foo").join(name).join("1.0");
+            fs::create_dir_all(&folder).unwrap();
+            fs::write(folder.join(format!("{}-1.0.jar", name)), content).unwrap();
+        }
+        let repository = ArtifactRepository::new_maven(maven_root);
+        let registry = FileSystemClaimRegistry::new(temp_folder(), Arc::new(test_gpg("dummy")), Arc::new(test_gpg("dummy")), 65536).unwrap();
+        let policy: Policy = toml::from_str("[[require]]\nclaim = \"reviewed\"").unwrap();
+        let mut trust_store = TrustStore::default();
+        trust_store.set(&registry.signing_key().unwrap().fingerprint, TrustedKey::new(1.0, Vec::new(), vec!("org.foo:app:".to_string())).unwrap());
+
+        let app_hash = repository.do_hash("org.foo:app:1.0", HashAlgorithm::Sha256).unwrap();
+        registry.sign_claim("org.foo:app:1.0", &app_hash, "reviewed", None, None).unwrap();
+        registry.sign_claim("org.foo:app:1.0", &app_hash, "reviewed", None, None).unwrap();
+        let tested = registry.sign_claim("org.foo:app:1.0", &app_hash, "tested", None, None).unwrap();
+        registry.revoke_claim("org.foo:app:1.0", std::slice::from_ref(&app_hash), &tested).unwrap();
+        // the key is only trusted for the app, whatever name its claim about the lib states
+        let lib_hash = repository.do_hash("org.foo:lib:1.0", HashAlgorithm::Sha256).unwrap();
+        registry.sign_claim("org.foo:app:1.0", &lib_hash, "reviewed", None, None).unwrap();
+
+        let dependencies = vec!(dependency("org.foo:app:1.0"), dependency("org.foo:lib:1.0"), dependency("org.foo:missing:1.0"));
+        let reports = check_dependencies(&repository, &registry, &dependencies, &policy, &trust_store);
+        assert_eq!(reports.len(), 3);
+
+        assert!(reports[0].passed(), "{}", reports[0]);
+        assert_eq!(reports[0].hash.as_ref(), Some(&app_hash));
+        assert_eq!(reports[0].claim_keys, vec!("reviewed"));
+        assert!(reports[0].to_string().starts_with("org.foo:app:1.0 (compile): ok\n"));
+
+        assert!(!reports[1].passed());
+        assert_eq!(reports[1].claim_keys, vec!("reviewed"));
+        assert_eq!(reports[1].problems, vec!("claim 'reviewed' requires 1 trusted signer(s) with a total trust of 0 - found 0 with a total trust of 0"));
+        assert!(reports[1].to_string().contains("FAILED"));
+
+        assert!(!reports[2].passed());
+        assert_eq!(reports[2].hash, None);
+        assert!(reports[2].problems[0].starts_with("error hashing the artifact"), "{:?}", reports[2].problems);
+
+        assert!(check_dependencies(&repository, &registry, &dependencies[..2], &Policy::default(), &trust_store).iter().all(|r| r.passed()));
+    }
+
+    #[test]
+    fn test_expected_digest() {
+        let cargo_home = temp_folder();
+        let cache = cargo_home.join("registry").join("cache").join("index.crates.io-6f17d22bba15001f");
+        fs::create_dir_all(&cache).unwrap();
+        fs::write(cache.join("serde-1.0.200.crate"), "serde").unwrap();
+        let repository = ArtifactRepository::new_cargo(cargo_home, false);
+        let registry = FileSystemClaimRegistry::new(temp_folder(), Arc::new(test_gpg("dummy")), Arc::new(test_gpg("dummy")), 65536).unwrap();
+
+        let serde = |algorithm: &'static Algorithm, values: Vec<Vec<u8>>| Dependency {
+            artifact_id: "serde:1.0.200".to_string(),
+            details: None,
+            expected_digest: Some(ExpectedDigest { algorithm, values }),
+        };
+        let sha256 = ring::digest::digest(&SHA256, b"serde").as_ref().to_vec();
+
+        let reports = check_dependencies(&repository, &registry, &[serde(&SHA256, vec!(sha256.clone()))], &Policy::default(), &TrustStore::default());
+        assert!(reports[0].passed(), "{}", reports[0]);
+
+        // other algorithms hash the artifact again
+        for algorithm in [&SHA1_FOR_LEGACY_USE_ONLY, &SHA384, &SHA512] {
+            let value = ring::digest::digest(algorithm, b"serde").as_ref().to_vec();
+            let reports = check_dependencies(&repository, &registry, &[serde(algorithm, vec!(value))], &Policy::default(), &TrustStore::default());
+            assert!(reports[0].passed(), "{}", reports[0]);
+            assert_eq!(reports[0].hash.as_ref().map(|h| &h.hash), Some(&sha256));
+        }
+
+        let mut other = sha256.clone();
+        other[0] ^= 1;
+        let reports = check_dependencies(&repository, &registry, &[serde(&SHA256, vec!(other.clone()))], &Policy::default(), &TrustStore::default());
+        assert!(!reports[0].passed());
+        assert_eq!(reports[0].hash.as_ref().map(|h| &h.hash), Some(&sha256));
+        assert_eq!(reports[0].problems, vec!(format!("sha256 mismatch - the expected digest is {}", to_hex_string(&other))));
+
+        // any of several digests matches, e.g. those of a Python package's distributions
+        let reports = check_dependencies(&repository, &registry, &[serde(&SHA256, vec!(other.clone(), sha256.clone()))], &Policy::default(), &TrustStore::default());
+        assert!(reports[0].passed(), "{}", reports[0]);
+        let reports = check_dependencies(&repository, &registry, &[serde(&SHA256, vec!(other.clone(), vec!(0; 32)))], &Policy::default(), &TrustStore::default());
+        assert_eq!(reports[0].problems, vec!(format!("sha256 mismatch - the expected digest is {} or {}", to_hex_string(&other), to_hex_string(&[0; 32]))));
+
+        let reports = check_dependencies(&repository, &registry, &[serde(&SHA384, vec!(vec!(0; 48)))], &Policy::default(), &TrustStore::default());
+        assert_eq!(reports[0].problems, vec!(format!("sha384 mismatch - the expected digest is {}", to_hex_string(&[0; 48]))));
+    }
+
+    #[test]
+    fn test_cargo_lockfile() {
+        let checksum = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let path = temp_file("Cargo.lock", &format!(r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "{}"
+
+[[package]]
+name = "log"
+version = "0.4.21"
+source = "sparse+https://index.crates.io/"
+
+[[package]]
+name = "internal"
+version = "2.0.0"
+source = "sparse+https://crates.example.com:8443/index/"
+checksum = "{}"
+
+[[package]]
+name = "forked"
+version = "1.0.0"
+source = "git+https://github.com/example/forked#0123456789abcdef"
+"#, checksum, checksum));
+
+        let dependencies = cargo_lockfile(&path).unwrap();
+        assert_eq!(artifact_ids(&dependencies), vec!("serde:1.0.200", "log:0.4.21", "internal:2.0.0:crates.example.com"));
+        assert_eq!(dependencies.dependencies[0].expected_digest, from_hex_string(checksum).map(|h| ExpectedDigest::sha256(vec!(h))));
+        assert_eq!(dependencies.dependencies[1].expected_digest, None);
+        let skipped: Vec<&str> = dependencies.skipped.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!("app 0.1.0", "forked 1.0.0"));
+
+        // version 1 lock files store the checksums in the metadata
+        let path = temp_file("Cargo.lock", &format!(r#"[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[metadata]
+"checksum serde 1.0.200 (registry+https://github.com/rust-lang/crates.io-index)" = "{}"
+"#, checksum));
+        assert!(cargo_lockfile(&path).unwrap().dependencies[0].expected_digest.is_some());
+
+        for invalid in ["abcd", "xyz", ""] {
+            let path = temp_file("Cargo.lock", &format!("[[package]]\nname = \"serde\"\nversion = \"1.0.200\"\nsource = \"sparse+https://index.crates.io/\"\nchecksum = \"{}\"\n", invalid));
+            assert!(cargo_lockfile(&path).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_maven_dependency_list() {
+        let path = temp_file("dependencies.txt", "\
+[INFO] --- maven-dependency-plugin:3.1.2:list (default-cli) @ demo ---
+[INFO]
+[INFO] The following files have been resolved:
+[INFO]    org.slf4j:slf4j-api:jar:1.7.30:compile
+[INFO]    junit:junit:jar:tests:4.13:test -- module junit
+   org.slf4j:slf4j-api:jar:1.7.30:compile
+[INFO] BUILD SUCCESS
+");
+        let dependencies = maven_dependency_list(&path).unwrap();
+        assert_eq!(dependencies.dependencies, vec!(
+            dependency("org.slf4j:slf4j-api:jar:1.7.30"),
+            Dependency { artifact_id: "junit:junit:jar:tests:4.13".to_string(), details: Some("test".to_string()), expected_digest: None },
+        ));
+    }
+
+    #[test]
+    fn test_npm_lockfile() {
+        let path = temp_file("package-lock.json", r#"{
+  "name": "demo",
+  "lockfileVersion": 3,
+  "packages": {
+    "": { "name": "demo", "workspaces": ["packages/*"] },
+    "node_modules/a": { "version": "1.0.0", "resolved": "https://registry.npmjs.org/a/-/a-1.0.0.tgz", "integrity": "sha1-AAECAwQFBgcICQoLDA0ODxAREhM=", "dev": true },
+    "node_modules/a/node_modules/@scope/b": { "version": "2.0.0", "resolved": "https://registry.npmjs.org/@scope/b/-/b-2.0.0.tgz" },
+    "node_modules/alias": { "name": "c", "version": "3.0.0", "resolved": "https://registry.npmjs.org/c/-/c-3.0.0.tgz" },
+    "node_modules/d": { "version": "1.0.0", "resolved": "git+ssh://git@github.com/x/d.git#abc" },
+    "node_modules/foo": { "resolved": "packages/foo", "link": true },
+    "node_modules/e": { "version": "1.0.0" },
+    "packages/foo": { "name": "foo", "version": "0.1.0" }
+  }
+}"#);
+        let dependencies = npm_lockfile(&path).unwrap();
+        assert_eq!(artifact_ids(&dependencies), vec!("a@1.0.0", "@scope/b@2.0.0", "c@3.0.0"));
+        assert_eq!(dependencies.dependencies[0].details.as_deref(), Some("dev"));
+        assert_eq!(dependencies.dependencies[0].expected_digest, Some(ExpectedDigest { algorithm: &SHA1_FOR_LEGACY_USE_ONLY, values: vec!((0..20).collect()) }));
+
+        let skipped: Vec<&str> = dependencies.skipped.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!("node_modules/d", "node_modules/e", "node_modules/foo", "packages/foo"));
+    }
+
+    #[test]
+    fn test_yarn_package_name() {
+        assert_eq!(yarn_package_name("lodash@^4.17.0"), Some("lodash"));
+        assert_eq!(yarn_package_name("@babel/code-frame@^7.0.0"), Some("@babel/code-frame"));
+        assert_eq!(yarn_package_name("alias@npm:lodash@^4.17.0"), Some("lodash"));
+        assert_eq!(yarn_package_name("alias@npm:@scope/x@1.0.0"), Some("@scope/x"));
+        assert_eq!(yarn_package_name("alias@npm:lodash"), Some("lodash"));
+        assert_eq!(yarn_package_name(""), None);
+        assert_eq!(yarn_package_name("@"), None);
+        assert_eq!(yarn_package_name("@@1.0.0"), None);
+        assert_eq!(yarn_package_name("lodash"), None);
+        assert_eq!(yarn_package_name("äbc@1.0.0"), Some("äbc"));
+        assert_eq!(yarn_package_name("ä"), None);
+    }
+
+    #[test]
+    fn test_yarn_lockfile() {
+        let path = temp_file("yarn.lock", r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+"@babel/code-frame@^7.0.0", "@babel/code-frame@^7.10.4":
+  version "7.10.4"
+  resolved "https://registry.yarnpkg.com/@babel/code-frame/-/code-frame-7.10.4.tgz#168da1a36e90da68ae8d49c0f1b48c7c6249213a"
+  integrity sha512-AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4OTo7PD0+Pw==
+  dependencies:
+    "@babel/highlight" "^7.10.4"
+
+lodash@^4.17.0:
+  version "4.17.21"
+  resolved "https://registry.yarnpkg.com/lodash/-/lodash-4.17.21.tgz"
+
+local@file:../local:
+  version "1.0.0"
+"#);
+        let dependencies = yarn_lockfile(&path).unwrap();
+        assert_eq!(artifact_ids(&dependencies), vec!("@babel/code-frame@7.10.4", "lodash@4.17.21"));
+        assert_eq!(dependencies.dependencies[0].expected_digest, Some(ExpectedDigest { algorithm: &SHA512, values: vec!((0..64).collect()) }));
+        assert_eq!(dependencies.dependencies[1].expected_digest, None);
+        assert_eq!(dependencies.skipped.len(), 1);
+
+        assert!(yarn_lockfile(&temp_file("yarn.lock", "\"\":\n  version \"1.0.0\"\n")).is_err());
+    }
+
+    const WHEEL_SHA256: &str = "ba59926159d2aa256eb8739b8da7e2b574b960e1202c6d624cbe981cef996c91";
+    const SDIST_SHA256: &str = "714772a9f82b2aeb4fa5f7092d00fe4ac4c9cdeb6800840b6ed39ea64c4d785a";
+
+    /// A Python repository with a wheel and an sdist of 'requests 2.31.0'
+    fn python_repository() -> PathBuf {
+        let root = temp_file("requests-2.31.0-py3-none-any.whl", "wheel").parent().unwrap().to_path_buf();
+        fs::create_dir_all(root.join("sdists")).unwrap();
+        fs::write(root.join("sdists/requests-2.31.0.tar.gz"), "sdist").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_requirements_txt() {
+        let repository = python_repository();
+        let other = temp_file("other.txt", "six==1.16.0 --hash=sha256:0000000000000000000000000000000000000000000000000000000000000000\n");
+        let path = temp_file("requirements.txt", &format!(r#"# a comment
+--index-url https://pypi.org/simple
+-r {}
+Requests[socks] == 2.31.0 ; python_version > "3.7" \
+    --hash=sha256:{} \
+    --hash sha256:{}  # the wheel
+requests==2.31.0 --hash=sha256:{}
+-e ./local
+pkg @ https://example.com/pkg-1.0.tar.gz
+"#, other.display(), SDIST_SHA256, WHEEL_SHA256, WHEEL_SHA256));
+
+        let dependencies = requirements_txt(&path, &repository).unwrap();
+        assert_eq!(artifact_ids(&dependencies), vec!("six==1.16.0", "requests-2.31.0-py3-none-any.whl"));
+        assert_eq!(dependencies.dependencies[1].details.as_deref(), Some("Requests==2.31.0"));
+        assert_eq!(dependencies.dependencies[1].expected_digest.as_ref().unwrap().values.len(), 2);
+        let skipped: Vec<&str> = dependencies.skipped.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!("./local", "pkg@https://example.com/pkg-1.0.tar.gz"));
+    }
+
+    #[test]
+    fn test_requirements_txt_invalid() {
+        let repository = python_repository();
+        let hash = format!(" --hash=sha256:{}\n", WHEEL_SHA256);
+        for requirement in &["requests>=2.31.0", "requests==2.*", "requests==2.31.0,<3", "==2.31.0"] {
+            let path = temp_file("requirements.txt", &format!("{}{}", requirement, hash));
+            assert!(requirements_txt(&path, &repository).is_err(), "{}", requirement);
+        }
+        for line in &["requests==2.31.0\n", "requests==2.31.0 --hash=sha256:abcd\n", "requests==2.31.0 --hash=sha256\n"] {
+            assert!(requirements_txt(&temp_file("requirements.txt", line), &repository).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_poetry_lockfile() {
+        let repository = python_repository();
+        let path = temp_file("poetry.lock", &format!(r#"
+[[package]]
+name = "requests"
+version = "2.31.0"
+files = [
+    {{file = "requests-2.31.0-py3-none-any.whl", hash = "sha256:{}"}},
+    {{file = "requests-2.31.0.tar.gz", hash = "md5:00"}},
+]
+
+[[package]]
+name = "six"
+version = "1.16.0"
+
+[package.source]
+type = "legacy"
+url = "https://example.com/simple"
+
+[[package]]
+name = "local"
+version = "1.0.0"
+
+[package.source]
+type = "directory"
+url = "../local"
+
+[metadata.files]
+six = [
+    {{file = "six-1.16.0.tar.gz", hash = "sha256:{}"}},
+]
+"#, WHEEL_SHA256, SDIST_SHA256));
+
+        let dependencies = poetry_lockfile(&path, &repository).unwrap();
+        assert_eq!(artifact_ids(&dependencies), vec!("requests-2.31.0-py3-none-any.whl", "six==1.16.0"));
+        assert_eq!(dependencies.dependencies[1].expected_digest, Some(ExpectedDigest::sha256(vec!(from_hex_string(SDIST_SHA256).unwrap()))));
+        assert_eq!(dependencies.skipped.len(), 1);
+        assert_eq!(dependencies.skipped[0].name, "local==1.0.0");
+    }
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use log::*;
+
+use crate::err::*;
+
+
+/// The kinds of keys claims can be signed with. Each has its own signature format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    /// OpenPGP keys, used by gpg
+    OpenPgp,
+    /// plain Ed25519 key files
+    Ed25519,
+    /// SSH keys, with signatures in the format of 'ssh-keygen -Y sign'
+    Ssh,
+    /// minisign keys - claims can only be verified, not signed
+    Minisign,
+}
+
+impl std::fmt::Display for KeyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            KeyType::OpenPgp => "openpgp",
+            KeyType::Ed25519 => "ed25519",
+            KeyType::Ssh => "ssh",
+            KeyType::Minisign => "minisign",
+        })
+    }
+}
+
+/// The identity of a signer. The fingerprint format depends on the key type:
+///
+/// * OpenPGP: the v4 fingerprint, i.e. 40 hex digits
+/// * Ed25519: the SHA-256 of the public key, i.e. 64 hex digits
+/// * SSH: OpenSSH's SHA-256 fingerprint, i.e. 'SHA256:' and the base64 encoded hash, as shown by
+///   'ssh-keygen -l'
+/// * minisign: the key id, i.e. 16 hex digits as shown by minisign
+#[derive(Clone, Debug)]
+pub struct PublicKey {
+    pub key_type: KeyType,
+    pub fingerprint: String,
+    /// the signer's name - only OpenPGP certifies it, for other key types the signer chose it
+    ///  freely, see `has_verified_uid`
+    pub uid: Option<String>,
+}
+
+impl PublicKey {
+    pub fn new(key_type: KeyType, fingerprint: String) -> PublicKey {
+        //TODO check length, no blanks, valid format
+        PublicKey {
+            key_type,
+            fingerprint,
+            uid: None,
+        }
+    }
+
+    /// true if the uid is bound to the key by the key itself - an Ed25519 key file's name or an
+    ///  SSH key's comment is whatever its owner wrote into it
+    pub fn has_verified_uid(&self) -> bool {
+        self.key_type == KeyType::OpenPgp
+    }
+}
+
+/// A pair of paths, one pointing to a data file and the other pointing to the corresponding
+/// signature file
+#[derive(Debug)]
+pub struct SignedFilePath {
+    pub data_path: PathBuf,
+    pub sig_path: PathBuf,
+}
+
+impl SignedFilePath {
+    pub fn new(base_path: &Path, data_file_name: &str) -> SignedFilePath {
+        let sig_file_name: &str = &format!("{}.sig", data_file_name);
+
+        let data_path = base_path.join(data_file_name);
+        let sig_path = base_path.join(sig_file_name);
+
+        SignedFilePath {
+            data_path,
+            sig_path,
+        }
+    }
+
+    /// The signed file path for an existing data file, i.e. with the signature file next to it
+    pub fn for_data_file(data_path: &Path) -> Option<SignedFilePath> {
+        let base_path = data_path.parent()?;
+        let data_file_name = data_path.file_name()?.to_str()?;
+        Some(SignedFilePath::new(base_path, data_file_name))
+    }
+
+    /// Determines the signature file's format from its content
+    pub fn signature_format(&self) -> Result<KeyType, TrustChainError> {
+        let data = io_guarded!(fs::read(&self.sig_path), Io, "error reading signature {:?}", &self.sig_path);
+        let text = String::from_utf8_lossy(&data);
+        let text = text.trim_start();
+
+        if text.starts_with("-----BEGIN PGP SIGNATURE-----") || data.first().is_some_and(|b| b & 0x80 != 0) {
+            // armored or binary OpenPGP packets
+            Ok(KeyType::OpenPgp)
+        }
+        else if text.starts_with("-----BEGIN SSH SIGNATURE-----") {
+            Ok(KeyType::Ssh)
+        }
+        else if text.starts_with("untrusted comment:") {
+            Ok(KeyType::Minisign)
+        }
+        else if crate::ed25519::is_signature_file(&data) {
+            Ok(KeyType::Ed25519)
+        }
+        else {
+            err!(Generic, "The signature file {:?} has an unknown format", &self.sig_path)
+        }
+    }
+
+    pub fn is_signature_file(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| ext == "sig")
+    }
+
+    pub fn temp(data_file_name: &str) -> SignedFilePath {
+        SignedFilePath::new(&std::env::temp_dir(), data_file_name)
+    }
+
+    pub fn copy_to(&self, other: &SignedFilePath, kind_of_file: &str) -> Result<(), TrustChainError> {
+        io_guarded!(fs::copy(&self.data_path, &other.data_path), Io, "error copying {} from {:?} to {:?}", kind_of_file, &self.data_path, &other.data_path);
+        io_guarded!(fs::copy(&self.sig_path, &other.sig_path), Io, "error copying {} signature from {:?} to {:?}", kind_of_file, &self.sig_path, &other.sig_path);
+        Ok(())
+    }
+
+    pub fn create_data_file(&self, content: &str, kind_of_file: &str) -> Result<(), TrustChainError> {
+        let mut f = io_guarded!(fs::File::create(&self.data_path), Io, "error creating {} file {:?}", kind_of_file, &self.data_path);
+        io_guarded!(f.write_all(content.as_bytes()), Io, "error writing to {} file {:?}", kind_of_file, &self.data_path);
+        Ok(())
+    }
+
+    pub fn move_to(self, other: &SignedFilePath, kind_of_file: &str) -> Result<(), TrustChainError> {
+        debug!("moving {} {:?} to {:?}", kind_of_file, &self, &other);
+
+        self.copy_to(other, kind_of_file)?;
+
+        io_guarded!(fs::remove_file(&self.data_path), Io, "error removing temporary {} file {:?}", kind_of_file, &self.data_path);
+        io_guarded!(fs::remove_file(&self.sig_path), Io, "error removing temporary {} signature file {:?}", kind_of_file, &self.sig_path);
+
+        Ok(())
+    }
+}
+
+
+/// The result of successfully verifying a signature
+#[derive(Clone, Debug)]
+pub struct Verification {
+    /// the signer's primary key
+    pub key: PublicKey,
+    /// the signature's creation time, if known
+    pub created: Option<SystemTime>,
+    /// true if the signing key has expired in the meantime - the signature is still valid
+    pub key_expired: bool,
+}
+
+/// Creates detached signatures with the key that all claims and revocations created locally are
+///  signed with
+pub trait Signer: Send + Sync {
+    fn sign(&self, kind_of_file: &str, path: &SignedFilePath) -> Result<(), TrustChainError>;
+
+    /// The primary key that is used for signing
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError>;
+}
+
+/// Verifies detached signatures. A signature by a revoked key or an expired signature is an error.
+pub trait Verifier: Send + Sync {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError>;
+}
+
+/// Verifies signatures in any of the supported formats, so that a registry can hold claims that
+///  were signed with different backends
+pub struct MultiFormatVerifier {
+    pub openpgp: Arc<dyn Verifier>,
+    pub ed25519: Arc<dyn Verifier>,
+    pub ssh: Arc<dyn Verifier>,
+    pub minisign: Arc<dyn Verifier>,
+}
+
+impl Verifier for MultiFormatVerifier {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError> {
+        match path.signature_format()? {
+            KeyType::OpenPgp => self.openpgp.verify(path),
+            KeyType::Ed25519 => self.ed25519.verify(path),
+            KeyType::Ssh => self.ssh.verify(path),
+            KeyType::Minisign => self.minisign.verify(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature_format(signature: &[u8]) -> Result<KeyType, TrustChainError> {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        let path = SignedFilePath::new(&folder, "claim");
+        fs::write(&path.sig_path, signature).unwrap();
+        path.signature_format()
+    }
+
+    #[test]
+    fn test_signature_format() {
+        assert!(matches!(signature_format(b"-----BEGIN PGP SIGNATURE-----\n").unwrap(), KeyType::OpenPgp));
+        assert!(matches!(signature_format(&[0x88, 0x75, 0x04]).unwrap(), KeyType::OpenPgp));
+        assert!(matches!(signature_format(b"\n-----BEGIN SSH SIGNATURE-----\n").unwrap(), KeyType::Ssh));
+        assert!(matches!(signature_format(b"untrusted comment: signature from minisign secret key\n").unwrap(), KeyType::Minisign));
+        let ed25519 = b"algorithm = \"ed25519\"\nname = \"CI\"\npublic-key = \"\"\ncreated = 0\nsignature = \"\"\n";
+        assert!(matches!(signature_format(ed25519).unwrap(), KeyType::Ed25519));
+        assert!(signature_format(b"").is_err());
+        assert!(signature_format(b"algorithm = \"rsa\"\n").is_err());
+    }
+
+    #[test]
+    fn test_signed_file_path() {
+        let path = SignedFilePath::for_data_file(Path::new("/registry/abcd/claim-1")).unwrap();
+        assert_eq!(path.data_path, Path::new("/registry/abcd/claim-1"));
+        assert_eq!(path.sig_path, Path::new("/registry/abcd/claim-1.sig"));
+        assert!(SignedFilePath::is_signature_file(&path.sig_path));
+        assert!(!SignedFilePath::is_signature_file(&path.data_path));
+    }
+}
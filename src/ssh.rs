@@ -0,0 +1,237 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use log::*;
+use ssh_key::{HashAlg, SshSig};
+
+use crate::config::SshConfig;
+use crate::err::*;
+use crate::signing::{KeyType, PublicKey, SignedFilePath, Signer, Verification, Verifier};
+
+
+/// the namespace of all signatures - signatures made for other purposes, e.g. git commits,
+///  are not valid for claims
+const NAMESPACE: &str = "trust-chain-checker";
+
+
+/// Signs claims with an SSH key by invoking 'ssh-keygen -Y sign', so that the key may be
+///  protected by a passphrase or held by an SSH agent
+pub struct SshSigner {
+    executable: PathBuf,
+    key_file: PathBuf,
+}
+
+impl SshSigner {
+    pub fn new(config: &SshConfig) -> Result<SshSigner, TrustChainError> {
+        match &config.key_file {
+            Some(key_file) => Ok(SshSigner {
+                executable: config.executable.clone().unwrap_or_else(|| PathBuf::from("ssh-keygen")),
+                key_file: key_file.clone(),
+            }),
+            None => err!(Config, "no SSH key file configured"),
+        }
+    }
+
+    /// ssh-keygen accepts either the private key or - if the private key is in an agent - the
+    ///  public key
+    fn public_key_file(&self) -> PathBuf {
+        if self.key_file.extension().is_some_and(|ext| ext == "pub") {
+            self.key_file.clone()
+        }
+        else {
+            let mut result = self.key_file.clone().into_os_string();
+            result.push(".pub");
+            PathBuf::from(result)
+        }
+    }
+}
+
+impl Signer for SshSigner {
+    fn sign(&self, kind_of_file: &str, path: &SignedFilePath) -> Result<(), TrustChainError> {
+        let mut cmd = Command::new(&self.executable);
+        cmd.arg("-Y").arg("sign");
+        cmd.arg("-f").arg(&self.key_file);
+        cmd.arg("-n").arg(NAMESPACE);
+        cmd.arg(&path.data_path);
+
+        run_command!(Generic, format!("error signing {} in {:?} with SSH key {:?}", kind_of_file, &path.data_path, &self.key_file), cmd);
+
+        // ssh-keygen writes the signature next to the data file
+        let mut written = path.data_path.clone().into_os_string();
+        written.push(".sig");
+        let written = PathBuf::from(written);
+        if written != path.sig_path {
+            io_guarded!(fs::rename(&written, &path.sig_path), Io, "error moving {} signature {:?} to {:?}", kind_of_file, &written, &path.sig_path);
+        }
+        Ok(())
+    }
+
+    /// The key's comment in the public key file serves as the uid
+    fn signing_key(&self) -> Result<PublicKey, TrustChainError> {
+        let path = self.public_key_file();
+        let public_key = io_guarded!(ssh_key::PublicKey::read_openssh_file(&path), Config, "error reading SSH public key {:?}", &path);
+
+        let mut result = PublicKey::new(KeyType::Ssh, public_key.fingerprint(HashAlg::Sha256).to_string());
+        result.uid = Some(public_key.comment().trim().to_string()).filter(|c| !c.is_empty());
+        Ok(result)
+    }
+}
+
+/// Verifies signatures in the format of 'ssh-keygen -Y sign', cf. OpenSSH's PROTOCOL.sshsig. The
+///  signatures contain the public key, so no list of allowed signers is needed - whether a key is
+///  trusted is decided by the trust store.
+pub struct SshVerifier;
+
+impl Verifier for SshVerifier {
+    fn verify(&self, path: &SignedFilePath) -> Result<Verification, TrustChainError> {
+        let sig_data = io_guarded!(fs::read(&path.sig_path), Io, "error reading signature {:?}", &path.sig_path);
+        let signature = match SshSig::from_pem(&sig_data) {
+            Ok(s) => s,
+            Err(e) => return err!(Generic, "The signature file {:?} does not contain a valid SSH signature: {}", &path.sig_path, e),
+        };
+        if signature.namespace() != NAMESPACE {
+            return err!(InvalidSignature, "The signature {:?} was made for {:?} rather than for claims", &path.sig_path, signature.namespace());
+        }
+
+        let data = io_guarded!(fs::read(&path.data_path), Io, "error reading {:?}", &path.data_path);
+        let public_key = ssh_key::PublicKey::from(signature.public_key().clone());
+        if let Err(e) = public_key.verify(NAMESPACE, &data, &signature) {
+            error!("The signature {:?} is not a valid signature for {:?} - this may indicate an attack", &path.sig_path, &path.data_path);
+            return err!(InvalidSignature, "The signature {:?} is not a valid signature for {:?} - this may indicate an attack ({})", &path.sig_path, &path.data_path, e);
+        }
+
+        Ok(Verification {
+            key: PublicKey::new(KeyType::Ssh, public_key.fingerprint(HashAlg::Sha256).to_string()),
+            // neither the creation time nor an expiration is part of the signature
+            created: None,
+            key_expired: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use uuid::Uuid;
+
+    const DATA: &str = "hello\n";
+
+    /// signatures of DATA made with 'ssh-keygen -Y sign -n trust-chain-checker', with the
+    ///  fingerprints of their keys as shown by 'ssh-keygen -l'
+    const SIGNATURES: [(&str, &str); 4] = [
+        ("SHA256:w2kEKdVnyT0OE6YwayL8VtyfbSn8crjUs4aRzoIsqEw", "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAgQqT1ytG5l4+hUc3rYppmRoFq2H
+2WPctTgu0cukGtvSIAAAATdHJ1c3QtY2hhaW4tY2hlY2tlcgAAAAAAAAAGc2hhNTEyAAAA
+UwAAAAtzc2gtZWQyNTUxOQAAAEB82OlROsRLK5L3BPjOB/WRbne3SR9ULLd352sDvSle4v
+Vp/poqZbI4KYDCovEw/jyosve/HbuEYm+QEWVgtaAC
+-----END SSH SIGNATURE-----
+"),
+        ("SHA256:g8bwje1xPQBCMNlkQsfs2FifzpzKmWrAkWnEcR/qSNg", "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAAGgAAAATZWNkc2Etc2hhMi1uaXN0cDI1NgAAAAhuaXN0cDI1NgAAAE
+EELP6Zs818xvIKBRHiod9eitjVExp2UyfUWx0fPhLHihP1lh96sOAz2hyBYg5XmHDcSOGU
+KjHou87V8K43G41AiQAAABN0cnVzdC1jaGFpbi1jaGVja2VyAAAAAAAAAAZzaGE1MTIAAA
+BkAAAAE2VjZHNhLXNoYTItbmlzdHAyNTYAAABJAAAAIQDp/BEtJtvMN/ynQOr8/ABN2z5v
+2IHtHlhfQF9owuH3gAAAACAPCGf2tLiJ6/TIRIoXgjBjYOAxZJMoqkqdgQwChhXG+g==
+-----END SSH SIGNATURE-----
+"),
+        ("SHA256:UQ5m60ngNhFqz+EPOU1aExGgR0ubi2jWXXAGjiR31MM", "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAAIgAAAATZWNkc2Etc2hhMi1uaXN0cDM4NAAAAAhuaXN0cDM4NAAAAG
+EEQKDaXlDIwqeBdnpn0ILE+OS5HC1c41oSTBU+CwrTFgRUyR3mXM4UQLCqUztrhhGumkjy
+2YX0vjkbWBBdnb89fRf1BYEOwX/CzaMl8SAVxPFgR7b3m0aI6VZtwcXHvXdOAAAAE3RydX
+N0LWNoYWluLWNoZWNrZXIAAAAAAAAABnNoYTUxMgAAAIMAAAATZWNkc2Etc2hhMi1uaXN0
+cDM4NAAAAGgAAAAwetH6yHIGS3f0bIg1Hh13oSsScThi2Yhml+3JPwVjS+fnz8qpQT0cjM
+g3lD8tJIGaAAAAMHQVs4sUBImn0wLLozUZqsNhc3EEmIRCDGqSxUfOLX9DxCBj2OQ/BbcH
+3MdFmRjrFg==
+-----END SSH SIGNATURE-----
+"),
+        ("SHA256:ol8sW+Lq194TbIj04qdokAPwMjiD03nW2AZ3FmZwkUI", "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAARcAAAAHc3NoLXJzYQAAAAMBAAEAAAEBALNCsCiQ9kFeOD/G+RhkwO
+RwpkvGlTaXFwmtweihdBntPftBi0/jCIVW3LT4TWHuIpAWKGp90bOTb49rmqYEusqqCMcf
+Ou5yJXTNu6X5RA/T5GgzcK7WKiY/mL4lORW+oB2/nV4C6ovyK8tM5IY8jGVcBqB0BodIXC
+X0MdRXM/GBGlt3pI0a0sKQ1VJHd7N5GzPm3Ba7npsyL8xmR1y8tNE+LdySVhaguBHuf9ix
+CYLLkf5ILiw3njjVBo3GZkzguuOOfcYGiQbMWbpFCqkZ+cBm0T2ErcG+BSTdyhcTAzwPkx
+MSpKMRGF3oe1wJOlNZxpo5bLM8mPS73cnjxwse2isAAAATdHJ1c3QtY2hhaW4tY2hlY2tl
+cgAAAAAAAAAGc2hhNTEyAAABFAAAAAxyc2Etc2hhMi01MTIAAAEATDGvhavbOyicI0DGuY
++WhYP/OnluTziFhjqLRoMYblec/yYun/D3IeBJ2ZwWpRPrrm1Z8MTAFlAQ7+gN7x/wn/Zj
+MNL2VIICq+xitqh744wn112a9EtYuLxgwrNmDnsk4VkkWfY7+dvCCQkaNNIgRuGUhQzQ8j
+h2jJN/ZqZyvXqWCl7apFe2/8LKmAKrGkdjgBrJnzhIir6O/J5EUkqQmNyqE+ElUZcYwkF/
+Gn1pr/COzESreEFAS8zmaiJ2jNvM1NkXJBHxLGUH70PsLjqTEWAugeI22mFPGmiXf9CrBf
+hLTlUNyspfFT3r+D5fx6BfZ7gmzMz2zoqgtJosVUmCCw==
+-----END SSH SIGNATURE-----
+"),
+    ];
+
+    /// a signature of DATA with the Ed25519 key, made for git
+    const GIT_SIGNATURE: &str = "\
+-----BEGIN SSH SIGNATURE-----
+U1NIU0lHAAAAAQAAADMAAAALc3NoLWVkMjU1MTkAAAAgQqT1ytG5l4+hUc3rYppmRoFq2H
+2WPctTgu0cukGtvSIAAAADZ2l0AAAAAAAAAAZzaGE1MTIAAABTAAAAC3NzaC1lZDI1NTE5
+AAAAQGClgcabtwGma70pkjIa2GXzWspNBz9zxtTmjOoY7Sn2/d6kr8S5U+XYV7F1y4Q85m
+pnM8GshbsfOy1TnQW+0QY=
+-----END SSH SIGNATURE-----
+";
+
+    const PUBLIC_KEY: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIEKk9crRuZePoVHN62KaZkaBath9lj3LU4LtHLpBrb0i ci@example.org\n";
+
+    fn temp_folder() -> std::path::PathBuf {
+        let folder = std::env::temp_dir().join(format!("trust-chain-checker-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    fn verify(data: &str, signature: &str) -> Result<Verification, TrustChainError> {
+        let path = SignedFilePath::new(&temp_folder(), "data.txt");
+        fs::write(&path.data_path, data).unwrap();
+        fs::write(&path.sig_path, signature).unwrap();
+        SshVerifier.verify(&path)
+    }
+
+    #[test]
+    fn test_verify() {
+        for (fingerprint, signature) in SIGNATURES {
+            let verification = verify(DATA, signature).unwrap();
+            assert_eq!(verification.key.key_type, KeyType::Ssh);
+            assert_eq!(verification.key.fingerprint, fingerprint);
+            assert_eq!(verification.key.uid, None);
+
+            let e = verify("hello!\n", signature).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn test_namespace() {
+        let e = verify(DATA, GIT_SIGNATURE).unwrap_err();
+        assert!(matches!(e.kind, TrustChainErrorKind::InvalidSignature), "{:?}", e);
+        assert!(e.description.contains("\"git\""), "{:?}", e);
+    }
+
+    #[test]
+    fn test_invalid_signature_file() {
+        let truncated: String = SIGNATURES[0].1.lines().take(3).map(|l| format!("{}\n", l)).collect::<String>() + "-----END SSH SIGNATURE-----\n";
+        for signature in ["", "-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----\n",
+                "-----BEGIN SSH SIGNATURE-----\nU1NIU0lHAAAAAg==\n-----END SSH SIGNATURE-----\n", &truncated] {
+            let e = verify(DATA, signature).unwrap_err();
+            assert!(matches!(e.kind, TrustChainErrorKind::Generic), "{:?}", e);
+        }
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let folder = temp_folder();
+        fs::write(folder.join("id_ed25519.pub"), PUBLIC_KEY).unwrap();
+        for key_file in ["id_ed25519", "id_ed25519.pub"] {
+            let signer = SshSigner::new(&SshConfig { key_file: Some(folder.join(key_file)), ..SshConfig::default() }).unwrap();
+            let key = signer.signing_key().unwrap();
+            assert_eq!(key.fingerprint, SIGNATURES[0].0);
+            assert_eq!(key.uid.as_deref(), Some("ci@example.org"));
+        }
+        assert!(SshSigner::new(&SshConfig { key_file: Some(Path::new("/nonexistent/id_ed25519").to_path_buf()), ..SshConfig::default() }).unwrap().signing_key().is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::err::*;
+
+
+/// How much claims signed by a given key count, stored locally as a TOML file:
+///
+/// ```toml
+/// [keys.608ED3F630C2E9EA934B96006C1298EFFCB624D9]
+/// weight = 1.0
+/// claims = ["reviewed"]
+/// artifacts = ["org.example:"]
+/// ```
+///
+/// Keys that are not in the trust store have a trust level of 0.0.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TrustStore {
+    keys: BTreeMap<String, TrustedKey>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TrustedKey {
+    /// between 0.0 (no trust at all) and 1.0 (full trust)
+    pub weight: f64,
+    /// the claim keys the trust is restricted to - all claims if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub claims: Vec<String>,
+    /// prefixes of the artifact names the trust is restricted to, e.g. a Maven group id - all
+    ///  artifacts if empty. They are matched against the name the artifact is checked under,
+    ///  never against the name stated in a claim.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+}
+
+impl TrustedKey {
+    pub fn new(weight: f64, claims: Vec<String>, artifacts: Vec<String>) -> Result<TrustedKey, TrustChainError> {
+        if !(0.0..=1.0).contains(&weight) {
+            return err!(Config, "invalid trust weight {} - it must be between 0.0 and 1.0", weight);
+        }
+        Ok(TrustedKey {
+            weight,
+            claims,
+            artifacts,
+        })
+    }
+
+    fn applies_to(&self, claim_key: &str, artifact_name: Option<&str>) -> bool {
+        (self.claims.is_empty() || self.claims.iter().any(|c| c == claim_key))
+            && (self.artifacts.is_empty() || artifact_name.is_some_and(|n| self.artifacts.iter().any(|a| n.starts_with(a.as_str()))))
+    }
+}
+
+impl TrustStore {
+    /// Reads the trust store, returning an empty one if the file does not exist
+    pub fn load(path: &Path) -> Result<TrustStore, TrustChainError> {
+        if !path.exists() {
+            debug!("trust store {:?} does not exist", path);
+            return Ok(TrustStore::default());
+        }
+
+        let s = io_guarded!(fs::read_to_string(path), Io, "error reading trust store {:?}", path);
+        let mut store: TrustStore = io_guarded!(toml::from_str(&s), Config, "error parsing trust store {:?}", path);
+
+        // fingerprints may have been edited by hand
+        store.keys = store.keys.into_iter()
+            .map(|(fingerprint, key)| (normalize_fingerprint(&fingerprint), key))
+            .collect();
+        for (fingerprint, key) in &store.keys {
+            if !(0.0..=1.0).contains(&key.weight) {
+                return err!(Config, "invalid trust weight {} for {} in trust store {:?}", key.weight, fingerprint, path);
+            }
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TrustChainError> {
+        if let Some(folder) = path.parent() {
+            io_guarded!(fs::create_dir_all(folder), Io, "error creating folder {:?} for the trust store", folder);
+        }
+        let s = io_guarded!(toml::to_string_pretty(self), Config, "error serializing the trust store");
+        io_guarded!(fs::write(path, s), Io, "error writing trust store {:?}", path);
+        Ok(())
+    }
+
+    pub fn set(&mut self, fingerprint: &str, key: TrustedKey) {
+        self.keys.insert(normalize_fingerprint(fingerprint), key);
+    }
+
+    /// returns false if the key was not in the trust store
+    pub fn remove(&mut self, fingerprint: &str) -> bool {
+        self.keys.remove(&normalize_fingerprint(fingerprint)).is_some()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=(&String, &TrustedKey)> {
+        self.keys.iter()
+    }
+
+    /// The trust in a claim signed by a given key, taking into account the key's scopes.
+    ///  `artifact_name` is the name the checker itself identified the artifact by, e.g. its id in
+    ///  the artifact repository - claims state a name as well, but it is chosen by the signer. Keys
+    ///  that are restricted to some artifacts have no trust if the name is unknown.
+    pub fn trust_level(&self, fingerprint: &str, claim_key: &str, artifact_name: Option<&str>) -> f64 {
+        match self.keys.get(&normalize_fingerprint(fingerprint)) {
+            Some(key) if key.applies_to(claim_key, artifact_name) => key.weight,
+            _ => 0.0,
+        }
+    }
+}
+
+impl std::fmt::Display for TrustedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.weight)?;
+        if !self.claims.is_empty() {
+            write!(f, ", claims: {}", self.claims.join(", "))?;
+        }
+        if !self.artifacts.is_empty() {
+            write!(f, ", artifacts: {}", self.artifacts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// gpg reports fingerprints as upper case hex without blanks, but they are often written in groups.
+///  Other fingerprints, e.g. OpenSSH's 'SHA256:...', are base64 encoded and therefore case
+///  sensitive.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    let result = fingerprint.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    if result.chars().all(|c| c.is_ascii_hexdigit()) {
+        result.to_uppercase()
+    }
+    else {
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trust_level_scopes() {
+        let mut store = TrustStore::default();
+        store.set("608e d3f6", TrustedKey::new(0.5, vec!("reviewed".to_string()), vec!("org.foo:".to_string())).unwrap());
+        store.set("ABCD", TrustedKey::new(1.0, Vec::new(), Vec::new()).unwrap());
+
+        assert_eq!(store.trust_level("608ED3F6", "reviewed", Some("org.foo:bar:1.0")), 0.5);
+        assert_eq!(store.trust_level("608ED3F6", "reviewed", Some("org.bar:foo:1.0")), 0.0);
+        assert_eq!(store.trust_level("608ED3F6", "reviewed", None), 0.0);
+        assert_eq!(store.trust_level("608ED3F6", "audited", Some("org.foo:bar:1.0")), 0.0);
+
+        assert_eq!(store.trust_level("abcd", "audited", None), 1.0);
+        assert_eq!(store.trust_level("1234", "reviewed", Some("org.foo:bar:1.0")), 0.0);
+    }
+
+    #[test]
+    fn test_ssh_fingerprint() {
+        let mut store = TrustStore::default();
+        store.set("SHA256:w2kEKdVnyT0OE6YwayL8VtyfbSn8crjUs4aRzoIsqEw", TrustedKey::new(0.5, Vec::new(), Vec::new()).unwrap());
+
+        assert_eq!(store.keys().next().unwrap().0, "SHA256:w2kEKdVnyT0OE6YwayL8VtyfbSn8crjUs4aRzoIsqEw");
+        assert_eq!(store.trust_level("SHA256:w2kEKdVnyT0OE6YwayL8VtyfbSn8crjUs4aRzoIsqEw", "reviewed", None), 0.5);
+        assert_eq!(store.trust_level("SHA256:W2KEKDVNYT0OE6YWAYL8VTYFBSN8CRJUS4ARZOISQEW", "reviewed", None), 0.0);
+        assert_eq!(store.trust_level("sha256:w2kekdvnyt0oe6ywayl8vtyfbsn8crjus4arzoisqew", "reviewed", None), 0.0);
+
+        assert_eq!(normalize_fingerprint("608e d3f6"), "608ED3F6");
+        assert_eq!(normalize_fingerprint("SHA256:abc+/def"), "SHA256:abc+/def");
+    }
+
+    #[test]
+    fn test_invalid_weight() {
+        assert!(TrustedKey::new(1.5, Vec::new(), Vec::new()).is_err());
+        assert!(TrustedKey::new(-0.1, Vec::new(), Vec::new()).is_err());
+    }
+}
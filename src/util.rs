@@ -12,6 +12,17 @@ pub fn write_hex_bytes<W>(w: &mut W, bytes: &[u8]) where W: std::fmt::Write {
     }
 }
 
+/// Parses hex digits in either case - nothing else, in particular no sign, is accepted
+pub fn from_hex_string(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 == 1 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i+2], 16).ok())
+        .collect()
+}
+
 pub fn write_output(s: &str) {
     println!("{}", s);
 }
@@ -26,4 +37,25 @@ impl<T: Deref> OptionDeref<T> for Option<T> {
     fn derefed(&self) -> Option<&T::Target> {
         self.as_ref().map(Deref::deref)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        assert_eq!(from_hex_string("00ff7A"), Some(vec!(0x00, 0xff, 0x7a)));
+        assert_eq!(to_hex_string(&[0x00, 0xff, 0x7a]), "00ff7a");
+        assert_eq!(from_hex_string(""), Some(vec!()));
+    }
+
+    #[test]
+    fn test_invalid_hex() {
+        assert_eq!(from_hex_string("abc"), None);
+        assert_eq!(from_hex_string("+a"), None);
+        assert_eq!(from_hex_string("-a"), None);
+        assert_eq!(from_hex_string("0g"), None);
+        assert_eq!(from_hex_string("ä0"), None);
+    }
+}